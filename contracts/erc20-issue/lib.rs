@@ -6,14 +6,20 @@ use ink_lang as ink;
 #[ink::contract]
 mod erc20 {
     use erc20_trait::{Error as IError, IErc20, Result as IResult};
-    use ink_prelude::string::String;
+    use ink_env::hash::Blake2x256;
+    use ink_prelude::{string::String, vec::Vec};
     use ownership::Ownable;
+    use scale::Encode;
 
     #[cfg(not(feature = "ink-as-dependency"))]
     use ink_lang as ink;
     #[cfg(not(feature = "ink-as-dependency"))]
     use ink_storage::{collections::HashMap as StorageHashMap, lazy::Lazy};
 
+    /// Prefix of every permit payload of an issued token, followed by the token
+    /// address so a permit signed for one issued token is useless on the others.
+    pub const PERMIT_DOMAIN: &[u8] = b"PatraStore Permit";
+
     /// The ERC-20 error types.
     #[derive(Debug, PartialEq, Eq, scale::Encode)]
     #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
@@ -28,6 +34,10 @@ mod erc20 {
         OnlyOwnerAccess,
         InvalidNewOwner,
         NotBlacklistedUser,
+        /// Returned if the permit signature was not made by the owner.
+        InvalidSignature,
+        /// Returned if the permit deadline has passed.
+        PermitExpired,
     }
 
     /// The ERC-20 result type.
@@ -90,6 +100,8 @@ mod erc20 {
         owner: AccountId,
 
         blacklisted: StorageHashMap<AccountId, bool>,
+        /// Number of permits used by each owner.
+        nonces: StorageHashMap<AccountId, u64>,
     }
 
     /// Event emitted when a token transfer occurs.
@@ -178,6 +190,7 @@ mod erc20 {
                 pause: false,
                 owner: caller,
                 blacklisted: Default::default(),
+                nonces: Default::default(),
             };
             Self::env().emit_event(Transfer {
                 from: None,
@@ -419,6 +432,76 @@ mod erc20 {
             Ok(())
         }
 
+        /// Approves `spender` to spend up to `value` issued tokens of `owner` with an
+        /// off chain signature, e.g. letting PatraMaker pull collateral without a
+        /// separate approve transaction. The permit is valid until `deadline` and can
+        /// be submitted by any account.
+        ///
+        /// An `Approval` event is emitted.
+        ///
+        /// # Errors
+        ///
+        /// Returns `PermitExpired` error if the deadline has passed.
+        ///
+        /// Returns `InvalidSignature` error if the signature was not made by `owner`
+        /// over the payload with its current nonce.
+        #[ink(message)]
+        pub fn permit(
+            &mut self,
+            owner: AccountId,
+            spender: AccountId,
+            value: Balance,
+            deadline: Timestamp,
+            signature: Vec<u8>,
+        ) -> Result<()> {
+            if self.env().block_timestamp() > deadline {
+                return Err(Error::PermitExpired);
+            }
+            let nonce = self.nonce_of(owner);
+            let payload = self.permit_payload(owner, spender, value, nonce, deadline);
+            if self.recover_signer(&payload, &signature) != Some(owner) {
+                return Err(Error::InvalidSignature);
+            }
+            self.nonces.insert(owner, nonce + 1);
+            self.allowances.insert((owner, spender), value);
+            self.env().emit_event(Approval {
+                owner,
+                spender,
+                value,
+            });
+            Ok(())
+        }
+
+        /// Returns how many permits `owner` has used on this token, the nonce its
+        /// next permit is signed with.
+        #[ink(message)]
+        pub fn nonce_of(&self, owner: AccountId) -> u64 {
+            self.nonces.get(&owner).copied().unwrap_or(0)
+        }
+
+        /// Returns the bytes `owner` signs to approve `spender` for `value` of this
+        /// token, tied to its current `nonce` and to `deadline`.
+        #[ink(message)]
+        pub fn permit_payload(
+            &self,
+            owner: AccountId,
+            spender: AccountId,
+            value: Balance,
+            nonce: u64,
+            deadline: Timestamp,
+        ) -> Vec<u8> {
+            (
+                PERMIT_DOMAIN,
+                self.env().account_id(),
+                owner,
+                spender,
+                value,
+                nonce,
+                deadline,
+            )
+                .encode()
+        }
+
         /// Transfers `value` amount of tokens from the caller's account to account `to`.
         ///
         /// On success a `Transfer` event is emitted.
@@ -448,6 +531,27 @@ mod erc20 {
             Ok(())
         }
 
+        /// Returns the signer of `payload`, the blake2 hash of its compressed ecdsa
+        /// public key, or `None` if `signature` is not a valid 65 bytes recoverable
+        /// signature.
+        fn recover_signer(&self, payload: &[u8], signature: &[u8]) -> Option<AccountId> {
+            if signature.len() != 65 {
+                return None;
+            }
+            let message = secp256k1::Message::parse(&self.env().hash_bytes::<Blake2x256>(payload));
+            let mut rs = [0u8; 64];
+            rs.copy_from_slice(&signature[..64]);
+            let v = signature[64];
+            let recovery_id =
+                secp256k1::RecoveryId::parse(if v >= 27 { v - 27 } else { v }).ok()?;
+            let signature = secp256k1::Signature::parse(&rs);
+            let public = secp256k1::recover(&message, &signature, &recovery_id).ok()?;
+            let account = self
+                .env()
+                .hash_bytes::<Blake2x256>(&public.serialize_compressed());
+            Some(AccountId::from(account))
+        }
+
         fn only_owner(&self) {
             assert_eq!(self.env().caller(), self.owner);
         }
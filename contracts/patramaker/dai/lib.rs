@@ -5,7 +5,16 @@ use ink_lang as ink;
 
 #[ink::contract]
 mod erc20 {
-    use ink_prelude::string::String;
+    use ink_env::hash::Blake2x256;
+    use ink_prelude::{string::String, vec::Vec};
+    use scale::Encode;
+
+    #[cfg(not(feature = "ink-as-dependency"))]
+    use ink_storage::{collections::HashMap as StorageHashMap, lazy::Lazy};
+
+    /// Prefix of every dai permit payload, the dai contract address follows it so a
+    /// dai permit can not be replayed on another PatraStore token.
+    pub const PERMIT_DOMAIN: &[u8] = b"PatraStore Permit";
 
     /// The ERC-20 error types.
     #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
@@ -16,56 +25,147 @@ mod erc20 {
         InsufficientSupply,
         /// Returned if not enough allowance to fulfill a request is available.
         InsufficientAllowance,
+        /// Returned if the caller is not an authorised minter.
+        OnlyMinterAccess,
+        OnlyAdminAccess,
+        InvalidAmount,
+        /// Returned if the permit signature was not made by the owner.
+        InvalidSignature,
+        /// Returned if the permit deadline has passed.
+        PermitExpired,
     }
 
     /// The ERC-20 result type.
     pub type Result<T> = core::result::Result<T, Error>;
 
+    /// Event emitted when a token transfer occurs, `from` is `None` on mint and
+    /// `to` is `None` on burn.
+    #[ink(event)]
+    pub struct Transfer {
+        #[ink(topic)]
+        from: Option<AccountId>,
+        #[ink(topic)]
+        to: Option<AccountId>,
+        #[ink(topic)]
+        value: Balance,
+    }
+
+    /// Event emitted when an approval occurs that `spender` is allowed to withdraw
+    /// up to the amount of `value` tokens from `owner`.
+    #[ink(event)]
+    pub struct Approval {
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        spender: AccountId,
+        #[ink(topic)]
+        value: Balance,
+    }
+
+    #[ink(event)]
+    pub struct AddMinter {
+        #[ink(topic)]
+        minter: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct RemoveMinter {
+        #[ink(topic)]
+        minter: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct TransferAdmin {
+        #[ink(topic)]
+        from: AccountId,
+        #[ink(topic)]
+        to: AccountId,
+    }
+
     #[ink(storage)]
-    pub struct Erc20 {}
+    pub struct Erc20 {
+        /// Total token supply.
+        total_supply: Lazy<Balance>,
+        /// Mapping from owner to number of owned token.
+        balances: StorageHashMap<AccountId, Balance>,
+        /// Mapping of the token amount which an account is allowed to withdraw
+        /// from another account.
+        allowances: StorageHashMap<(AccountId, AccountId), Balance>,
+        /// Name of the token
+        name: Option<String>,
+        /// Symbol of the token
+        symbol: Option<String>,
+        /// Decimals of the token
+        decimals: Option<u8>,
+        /// Accounts allowed to mint and burn tokens.
+        minters: StorageHashMap<AccountId, bool>,
+        /// Manages the minters.
+        admin: AccountId,
+        /// Number of permits used by each owner.
+        nonces: StorageHashMap<AccountId, u64>,
+    }
 
     impl Erc20 {
         /// Creates a new ERC-20 contract with the specified initial supply.
         #[ink(constructor)]
         pub fn new(
-            _initial_supply: Balance,
-            _name: Option<String>,
-            _symbol: Option<String>,
-            _decimals: Option<u8>,
+            initial_supply: Balance,
+            name: Option<String>,
+            symbol: Option<String>,
+            decimals: Option<u8>,
         ) -> Self {
-            unimplemented!()
+            let caller = Self::env().caller();
+            let mut balances = StorageHashMap::new();
+            balances.insert(caller, initial_supply);
+            let instance = Self {
+                total_supply: Lazy::new(initial_supply),
+                balances,
+                allowances: StorageHashMap::new(),
+                name,
+                symbol,
+                decimals,
+                minters: StorageHashMap::new(),
+                admin: caller,
+                nonces: StorageHashMap::new(),
+            };
+            Self::env().emit_event(Transfer {
+                from: None,
+                to: Some(caller),
+                value: initial_supply,
+            });
+            instance
         }
 
         /// Returns the token name.
         #[ink(message, selector = "0x6b1bb951")]
         pub fn token_name(&self) -> Option<String> {
-            unimplemented!()
+            self.name.clone()
         }
 
         /// Returns the token symbol.
         #[ink(message, selector = "0xb42c3368")]
         pub fn token_symbol(&self) -> Option<String> {
-            unimplemented!()
+            self.symbol.clone()
         }
 
         /// Returns the token decimals.
         #[ink(message, selector = "0xc64b0eb2")]
         pub fn token_decimals(&self) -> Option<u8> {
-            unimplemented!()
+            self.decimals
         }
 
         /// Returns the total token supply.
         #[ink(message, selector = "0x143862ae")]
         pub fn total_supply(&self) -> Balance {
-            unimplemented!()
+            *self.total_supply
         }
 
         /// Returns the account balance for the specified `owner`.
         ///
         /// Returns `0` if the account is non-existent.
         #[ink(message, selector = "0xb7d968c9")]
-        pub fn balance_of(&self, _owner: AccountId) -> Balance {
-            unimplemented!()
+        pub fn balance_of(&self, owner: AccountId) -> Balance {
+            self.balances.get(&owner).copied().unwrap_or(0)
         }
 
         /// Transfers `value` amount of tokens from the caller's account to account `to`.
@@ -77,16 +177,17 @@ mod erc20 {
         /// Returns `InsufficientBalance` error if there are not enough tokens on
         /// the caller's account balance.
         #[ink(message, selector = "0x10d455c2")]
-        pub fn transfer(&mut self, _to: AccountId, _value: Balance) -> Result<()> {
-            unimplemented!()
+        pub fn transfer(&mut self, to: AccountId, value: Balance) -> Result<()> {
+            let from = self.env().caller();
+            self.transfer_from_to(from, to, value)
         }
 
         /// Returns the amount which `spender` is still allowed to withdraw from `owner`.
         ///
         /// Returns `0` if no allowance has been set `0`.
         #[ink(message, selector = "0xc04aa300")]
-        pub fn allowance(&self, _owner: AccountId, _spender: AccountId) -> Balance {
-            unimplemented!()
+        pub fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance {
+            self.allowances.get(&(owner, spender)).copied().unwrap_or(0)
         }
 
         /// Transfers `value` tokens on the behalf of `from` to the account `to`.
@@ -106,11 +207,18 @@ mod erc20 {
         #[ink(message, selector = "0xbb399017")]
         pub fn transfer_from(
             &mut self,
-            _from: AccountId,
-            _to: AccountId,
-            _value: Balance,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
         ) -> Result<()> {
-            unimplemented!()
+            let caller = self.env().caller();
+            let allowance = self.allowance(from, caller);
+            if allowance < value {
+                return Err(Error::InsufficientAllowance);
+            }
+            self.transfer_from_to(from, to, value)?;
+            self.allowances.insert((from, caller), allowance - value);
+            Ok(())
         }
 
         /// Allows `spender` to withdraw from the caller's account multiple times, up to
@@ -120,24 +228,241 @@ mod erc20 {
         ///
         /// An `Approval` event is emitted.
         #[ink(message, selector = "0x4ce0e831")]
-        pub fn approve(&mut self, _spender: AccountId, _value: Balance) -> Result<()> {
-            unimplemented!()
+        pub fn approve(&mut self, spender: AccountId, value: Balance) -> Result<()> {
+            let owner = self.env().caller();
+            self.allowances.insert((owner, spender), value);
+            self.env().emit_event(Approval {
+                owner,
+                spender,
+                value,
+            });
+            Ok(())
+        }
+
+        /// Approves `spender` to spend up to `value` dai of `owner` on a signature made
+        /// off chain, so a holder without DOT for fees can still let the savings
+        /// contract or a flash mint borrower pull its dai. Anyone may relay the signed
+        /// permit before `deadline`.
+        ///
+        /// An `Approval` event is emitted.
+        ///
+        /// # Errors
+        ///
+        /// Returns `PermitExpired` error if the deadline has passed.
+        ///
+        /// Returns `InvalidSignature` error if the signature was not made by `owner`
+        /// over the payload with its current nonce.
+        #[ink(message)]
+        pub fn permit(
+            &mut self,
+            owner: AccountId,
+            spender: AccountId,
+            value: Balance,
+            deadline: Timestamp,
+            signature: Vec<u8>,
+        ) -> Result<()> {
+            if self.env().block_timestamp() > deadline {
+                return Err(Error::PermitExpired);
+            }
+            let nonce = self.nonce_of(owner);
+            let payload = self.permit_payload(owner, spender, value, nonce, deadline);
+            if self.recover_signer(&payload, &signature) != Some(owner) {
+                return Err(Error::InvalidSignature);
+            }
+            self.nonces.insert(owner, nonce + 1);
+            self.allowances.insert((owner, spender), value);
+            self.env().emit_event(Approval {
+                owner,
+                spender,
+                value,
+            });
+            Ok(())
+        }
+
+        /// Returns the nonce the next dai permit of `owner` must be signed with.
+        #[ink(message)]
+        pub fn nonce_of(&self, owner: AccountId) -> u64 {
+            self.nonces.get(&owner).copied().unwrap_or(0)
+        }
+
+        /// Returns the SCALE encoded bytes `owner` signs to approve `spender` for
+        /// `value` dai with the given nonce and deadline.
+        #[ink(message)]
+        pub fn permit_payload(
+            &self,
+            owner: AccountId,
+            spender: AccountId,
+            value: Balance,
+            nonce: u64,
+            deadline: Timestamp,
+        ) -> Vec<u8> {
+            (
+                PERMIT_DOMAIN,
+                self.env().account_id(),
+                owner,
+                spender,
+                value,
+                nonce,
+                deadline,
+            )
+                .encode()
         }
 
         /// Issue a new amount of tokens
-        /// these tokens are deposited into the owner address
+        /// these tokens are deposited into the user address, only minter
         #[ink(message)]
-        pub fn mint(&mut self, _user: AccountId, _amount: Balance) -> Result<()> {
-            unimplemented!()
+        pub fn mint(&mut self, user: AccountId, amount: Balance) -> Result<()> {
+            self.only_minter()?;
+            assert_ne!(user, Default::default());
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            let user_balance = self.balance_of(user);
+            let total_supply = self
+                .total_supply
+                .checked_add(amount)
+                .ok_or(Error::InvalidAmount)?;
+            self.balances.insert(user, user_balance + amount);
+            *self.total_supply = total_supply;
+            self.env().emit_event(Transfer {
+                from: None,
+                to: Some(user),
+                value: amount,
+            });
+            Ok(())
         }
 
         /// Redeem tokens.
-        /// These tokens are withdrawn from the owner address
+        /// These tokens are withdrawn from the user address
         /// if the balance must be enough to cover the redeem
-        /// or the call will fail.
+        /// or the call will fail, only minter
         #[ink(message)]
-        pub fn burn(&mut self, _user: AccountId, _amount: Balance) -> Result<()> {
-            unimplemented!()
+        pub fn burn(&mut self, user: AccountId, amount: Balance) -> Result<()> {
+            self.only_minter()?;
+            if *self.total_supply < amount {
+                return Err(Error::InsufficientSupply);
+            }
+            let user_balance = self.balance_of(user);
+            if user_balance < amount {
+                return Err(Error::InsufficientBalance);
+            }
+            self.balances.insert(user, user_balance - amount);
+            *self.total_supply -= amount;
+            self.env().emit_event(Transfer {
+                from: Some(user),
+                to: None,
+                value: amount,
+            });
+            Ok(())
+        }
+
+        /// Authorise `minter` to mint and burn tokens, only admin
+        #[ink(message)]
+        pub fn add_minter(&mut self, minter: AccountId) -> Result<()> {
+            self.only_admin()?;
+            if self.minters.insert(minter, true).is_none() {
+                self.env().emit_event(AddMinter { minter });
+            }
+            Ok(())
+        }
+
+        /// Revoke `minter`, only admin
+        #[ink(message)]
+        pub fn remove_minter(&mut self, minter: AccountId) -> Result<()> {
+            self.only_admin()?;
+            if self.minters.take(&minter).is_some() {
+                self.env().emit_event(RemoveMinter { minter });
+            }
+            Ok(())
+        }
+
+        /// Whether `account` is an authorised minter.
+        #[ink(message)]
+        pub fn is_minter(&self, account: AccountId) -> bool {
+            self.minters.get(&account).copied().unwrap_or(false)
+        }
+
+        /// Returns the account managing the minters.
+        #[ink(message)]
+        pub fn admin(&self) -> AccountId {
+            self.admin
+        }
+
+        /// Hand the minter management over to `new_admin`, only admin
+        #[ink(message)]
+        pub fn transfer_admin(&mut self, new_admin: AccountId) -> Result<()> {
+            self.only_admin()?;
+            let from = self.admin;
+            self.admin = new_admin;
+            self.env().emit_event(TransferAdmin {
+                from,
+                to: new_admin,
+            });
+            Ok(())
+        }
+
+        /// Transfers `value` amount of tokens from the caller's account to account `to`.
+        ///
+        /// On success a `Transfer` event is emitted.
+        ///
+        /// # Errors
+        ///
+        /// Returns `InsufficientBalance` error if there are not enough tokens on
+        /// the caller's account balance.
+        fn transfer_from_to(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+        ) -> Result<()> {
+            let from_balance = self.balance_of(from);
+            if from_balance < value {
+                return Err(Error::InsufficientBalance);
+            }
+            self.balances.insert(from, from_balance - value);
+            let to_balance = self.balance_of(to);
+            self.balances.insert(to, to_balance + value);
+            self.env().emit_event(Transfer {
+                from: Some(from),
+                to: Some(to),
+                value,
+            });
+            Ok(())
+        }
+
+        /// Recovers the account that signed a dai permit. The account id is the blake2
+        /// hash of the compressed ecdsa public key, as derived by the `ecdsa` keyring;
+        /// `signature` is r, s and the recovery id, 65 bytes in total.
+        fn recover_signer(&self, payload: &[u8], signature: &[u8]) -> Option<AccountId> {
+            if signature.len() != 65 {
+                return None;
+            }
+            let message = secp256k1::Message::parse(&self.env().hash_bytes::<Blake2x256>(payload));
+            let mut rs = [0u8; 64];
+            rs.copy_from_slice(&signature[..64]);
+            let v = signature[64];
+            let recovery_id =
+                secp256k1::RecoveryId::parse(if v >= 27 { v - 27 } else { v }).ok()?;
+            let signature = secp256k1::Signature::parse(&rs);
+            let public = secp256k1::recover(&message, &signature, &recovery_id).ok()?;
+            let account = self
+                .env()
+                .hash_bytes::<Blake2x256>(&public.serialize_compressed());
+            Some(AccountId::from(account))
+        }
+
+        fn only_minter(&self) -> Result<()> {
+            if !self.is_minter(self.env().caller()) {
+                return Err(Error::OnlyMinterAccess);
+            }
+            Ok(())
+        }
+
+        fn only_admin(&self) -> Result<()> {
+            if self.env().caller() != self.admin {
+                return Err(Error::OnlyAdminAccess);
+            }
+            Ok(())
         }
     }
 }
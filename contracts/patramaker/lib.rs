@@ -2,10 +2,15 @@
 
 use ink_lang as ink;
 
+pub mod math;
+
+pub use self::patramaker::{FlashBorrower, PatraMaker};
+
 #[ink::contract]
 mod patramaker {
+    use crate::math::{self, Rounding};
     use dai::Erc20;
-    use ink_env::call::FromAccountId;
+    use ink_env::call::{build_call, utils::ReturnType, ExecutionInput, FromAccountId, Selector};
     use ink_prelude::vec::Vec;
     use ink_storage::{
         collections::HashMap as StorageMap,
@@ -16,18 +21,141 @@ mod patramaker {
     use primitive_types::U256;
 
     pub type CdpId = u32;
+    pub type IlkId = u32;
+    pub type AuctionId = u32;
+    pub type ProposalId = u32;
     pub type USD = u32;
 
     pub const DOTS: Balance = 10_000_000_000;
+    /// The native dot collateral type registered at construction.
+    pub const DOT_ILK: IlkId = 0;
     pub const DOT_PRICE_DECIMALS: u32 = 100;
+    /// Default window in which a price quote is considered fresh, one hour.
+    pub const DEFAULT_PRICE_TTL: Timestamp = 60 * 60 * 1000;
+    /// Precision of the stability fee rate accumulator.
+    pub const RATE_ONE: u128 = math::RAY;
+    /// Stability fee is expressed in basis points per year.
+    pub const FEE_DECIMALS: u32 = 10_000;
+    pub const YEAR: Timestamp = 365 * 24 * 60 * 60 * 1000;
+    /// Default number of blocks after which an auction must be restarted, one day.
+    pub const DEFAULT_AUCTION_TAIL: BlockNumber = 14_400;
+    /// Max number of entries returned by paginated queries.
+    pub const MAX_PAGE_SIZE: u32 = 100;
+    /// Default min time between an emergency shutdown and settlement, two days.
+    pub const DEFAULT_SHUTDOWN_WAIT: Timestamp = 2 * 24 * 60 * 60 * 1000;
+    /// Min delay between queueing and executing a param change, one minute.
+    pub const MIN_GOV_DELAY: Timestamp = 60 * 1000;
+    /// Max delay between queueing and executing a param change, thirty days.
+    pub const MAX_GOV_DELAY: Timestamp = 30 * 24 * 60 * 60 * 1000;
+    /// Window after its eta in which a queued param change can be executed, fourteen days.
+    pub const GOV_GRACE_PERIOD: Timestamp = 14 * 24 * 60 * 60 * 1000;
+    /// Max annual stability fee in basis points, 100%.
+    pub const MAX_STABILITY_FEE: u32 = FEE_DECIMALS;
+    /// Max liquidation penalty in basis points of the covered debt, 50%.
+    pub const MAX_LIQUIDATION_PENALTY: u32 = FEE_DECIMALS / 2;
+    /// Selector of `FlashBorrower::on_flash_loan`, the first four bytes of the blake2
+    /// hash of its path.
+    pub const ON_FLASH_LOAN: [u8; 4] = [0x38, 0x09, 0x6f, 0xfe];
+
+    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum Error {
+        /// Returned if the caller is not the admin or a price reporter.
+        Unauthorized,
+        CdpNotFound,
+        IlkNotFound,
+        /// Returned if the collateral token is already registered.
+        IlkExists,
+        NotIssuer,
+        /// Returned if more dai is repaid than the cdp owes.
+        RepayExceedsDebt,
+        /// Returned if the cdp is being auctioned.
+        AuctionRunning,
+        /// Returned if the cdp would fall below the min collateral ratio.
+        BelowMinCollateralRatio,
+        InsufficientCollateral,
+        /// Returned if the transferred value does not match the collateral amount.
+        InvalidCollateralAmount,
+        /// Returned if the collateral type or global debt ceiling would be exceeded.
+        DebtCeilingExceeded,
+        /// Returned if the cdp debt would be neither zero nor above the dust limit.
+        DustDebt,
+        /// Returned if the price feed has not been updated within the price ttl.
+        StalePrice,
+        ReporterExists,
+        ReporterNotFound,
+        InvalidPrice,
+        /// Returned if the quorum is zero or above the number of reporters, or the price
+        /// ttl is zero.
+        InvalidOracleParams,
+        /// Returned if the cdp is not below the min liquidation ratio.
+        NotLiquidatable,
+        AuctionNotFound,
+        /// Returned if the auction ran too long or its price reached zero and needs a redo.
+        AuctionExpired,
+        AuctionNotExpired,
+        PriceTooHigh,
+        InvalidAuctionParams,
+        /// Returned if the system has been shut down.
+        NotLive,
+        NotCaged,
+        NotSettled,
+        /// Returned if the cdp debt has not been skimmed yet.
+        DebtOutstanding,
+        /// Returned if the shutdown wait has not passed or debt and auctions remain.
+        SettleTooEarly,
+        /// Returned if more dai is redeemed than was outstanding at settlement.
+        InsufficientDai,
+        DaiMintFailed,
+        /// Returned if the dai token does not report its decimals.
+        DaiDecimalsUnknown,
+        /// Returned if a collateral token does not report its decimals.
+        TokenDecimalsUnknown,
+        DaiBurnFailed,
+        TransferFailed,
+        /// Returned if a valuation does not fit in 128 bits.
+        Overflow,
+        /// Returned if the min liquidation ratio would not stay between 100 and the min
+        /// collateral ratio.
+        InvalidRatio,
+        /// Returned if a param change is out of bounds.
+        InvalidParam,
+        ProposalNotFound,
+        /// Returned if the param change is executed before its eta.
+        ProposalNotReady,
+        /// Returned if the param change was not executed within the grace period.
+        ProposalExpired,
+        /// Returned if the flash mint is above the cap or reentered.
+        FlashMintRefused,
+        /// Returned if the surplus net of bad debt does not cover the savings interest.
+        InsufficientSurplus,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    /// Receiver of a dai flash mint.
+    #[ink::trait_definition]
+    pub trait FlashBorrower {
+        /// Called with `amount` dai minted to the receiver, it must approve `amount + fee`
+        /// to the maker which pulls and burns it once the call returns. Must return `true`
+        /// to accept the loan.
+        #[ink(message)]
+        fn on_flash_loan(
+            &mut self,
+            initiator: AccountId,
+            amount: Balance,
+            fee: Balance,
+            data: Vec<u8>,
+        ) -> bool;
+    }
 
     #[ink(event)]
     pub struct IssueDAI {
         #[ink(topic)]
         cdp_id: CdpId,
         #[ink(topic)]
+        ilk_id: IlkId,
         collateral: Balance,
-        #[ink(topic)]
         dai: Balance,
     }
 
@@ -51,6 +179,29 @@ mod patramaker {
         collateral_ratio: u32,
     }
 
+    #[ink(event)]
+    pub struct DrawDAI {
+        #[ink(topic)]
+        cdp_id: CdpId,
+        dai: Balance,
+        collateral_ratio: u32,
+    }
+
+    #[ink(event)]
+    pub struct RepayDAI {
+        #[ink(topic)]
+        cdp_id: CdpId,
+        dai: Balance,
+    }
+
+    #[ink(event)]
+    pub struct CloseCDP {
+        #[ink(topic)]
+        cdp_id: CdpId,
+        collateral: Balance,
+        dai: Balance,
+    }
+
     #[ink(event)]
     pub struct Withdraw {
         #[ink(topic)]
@@ -65,10 +216,302 @@ mod patramaker {
     pub struct Liquidate {
         #[ink(topic)]
         cdp_id: CdpId,
+        // collateral paid out to the keeper
+        collateral: Balance,
+        // debt covered by the keeper
+        dai: Balance,
+        // part of the penalty paid to the keeper
+        keeper_tip: Balance,
+        // part of the penalty paid to the system surplus
+        surplus: Balance,
+    }
+
+    #[ink(event)]
+    pub struct TransferCDP {
+        #[ink(topic)]
+        cdp_id: CdpId,
+        #[ink(topic)]
+        from: AccountId,
+        #[ink(topic)]
+        to: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct ApproveCDPOperator {
+        #[ink(topic)]
+        cdp_id: CdpId,
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        operator: Option<AccountId>,
+    }
+
+    #[ink(event)]
+    pub struct Kick {
+        #[ink(topic)]
+        auction_id: AuctionId,
+        #[ink(topic)]
+        cdp_id: CdpId,
+        collateral: Balance,
+        dai: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Take {
+        #[ink(topic)]
+        auction_id: AuctionId,
+        collateral: Balance,
+        dai: Balance,
+        price: u128,
+    }
+
+    #[ink(event)]
+    pub struct Redo {
+        #[ink(topic)]
+        auction_id: AuctionId,
+        top: u128,
+    }
+
+    #[ink(event)]
+    pub struct AuctionEnd {
+        #[ink(topic)]
+        auction_id: AuctionId,
+        rest_collateral: Balance,
+        rest_dai: Balance,
+    }
+
+    #[ink(event)]
+    pub struct AdjustAuction {
+        buf: u32,
+        curve: DecayCurve,
+        tail: BlockNumber,
+    }
+
+    #[ink(event)]
+    pub struct CollectFee {
+        #[ink(topic)]
+        cdp_id: CdpId,
+        fee: Balance,
+    }
+
+    #[ink(event)]
+    pub struct AdjustFee {
+        stability_fee: u32,
+    }
+
+    #[ink(event)]
+    pub struct AddIlk {
+        #[ink(topic)]
+        ilk_id: IlkId,
+        #[ink(topic)]
+        token: Option<AccountId>,
+    }
+
+    #[ink(event)]
+    pub struct AdjustIlk {
+        #[ink(topic)]
+        ilk_id: IlkId,
+        min_collateral_ratio: u32,
+        min_liquidation_ratio: u32,
+        debt_ceiling: Balance,
+    }
+
+    #[ink(event)]
+    pub struct AdjustDebtLimits {
+        debt_ceiling: Balance,
+        dust: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Shutdown {
+        caged_at: Timestamp,
+    }
+
+    #[ink(event)]
+    pub struct Skim {
+        #[ink(topic)]
+        cdp_id: CdpId,
+        collateral: Balance,
+        dai: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Yank {
+        #[ink(topic)]
+        auction_id: AuctionId,
+        #[ink(topic)]
+        cdp_id: CdpId,
+    }
+
+    #[ink(event)]
+    pub struct ReclaimCollateral {
         #[ink(topic)]
+        cdp_id: CdpId,
         collateral: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Settle {
+        dai_supply: Balance,
+    }
+
+    #[ink(event)]
+    pub struct RedeemDAI {
+        #[ink(topic)]
+        user: AccountId,
+        dai: Balance,
+    }
+
+    #[ink(event)]
+    pub struct AddReporter {
+        #[ink(topic)]
+        reporter: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct RemoveReporter {
         #[ink(topic)]
-        keeper_reward: Balance,
+        reporter: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct AdjustOracle {
+        quorum: u32,
+        price_ttl: Timestamp,
+    }
+
+    #[ink(event)]
+    pub struct BadDebt {
+        #[ink(topic)]
+        cdp_id: CdpId,
+        dai: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Heal {
+        dai: Balance,
+    }
+
+    #[ink(event)]
+    pub struct PayInterest {
+        #[ink(topic)]
+        savings: AccountId,
+        dai: Balance,
+    }
+
+    #[ink(event)]
+    pub struct FlashMint {
+        #[ink(topic)]
+        receiver: AccountId,
+        amount: Balance,
+        fee: Balance,
+    }
+
+    #[ink(event)]
+    pub struct AdjustFlashMint {
+        cap: Balance,
+        fee: u32,
+    }
+
+    #[ink(event)]
+    pub struct AdjustPenalty {
+        penalty: u32,
+        tip: Balance,
+        chip: u32,
+    }
+
+    #[ink(event)]
+    pub struct QueueChange {
+        #[ink(topic)]
+        proposal_id: ProposalId,
+        change: ParamChange,
+        eta: Timestamp,
+    }
+
+    #[ink(event)]
+    pub struct CancelChange {
+        #[ink(topic)]
+        proposal_id: ProposalId,
+    }
+
+    #[ink(event)]
+    pub struct ExecuteChange {
+        #[ink(topic)]
+        proposal_id: ProposalId,
+        change: ParamChange,
+    }
+
+    #[ink(event)]
+    pub struct SubmitPrice {
+        #[ink(topic)]
+        ilk_id: IlkId,
+        #[ink(topic)]
+        reporter: AccountId,
+        price: USD,
+    }
+
+    #[ink(event)]
+    pub struct UpdatePrice {
+        #[ink(topic)]
+        ilk_id: IlkId,
+        price: USD,
+        quotes: u32,
+    }
+
+    /// A risk param change that takes effect only after the governance delay.
+    #[derive(
+        Debug, PartialEq, Eq, Clone, Copy, scale::Encode, scale::Decode, SpreadLayout, PackedLayout,
+    )]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub enum ParamChange {
+        /// Min collateral ratio of a collateral type in percent.
+        Mcr(IlkId, u32),
+        /// Min liquidation ratio of a collateral type in percent.
+        Mlr(IlkId, u32),
+        /// Debt ceiling of a collateral type.
+        IlkCeiling(IlkId, Balance),
+        /// Global debt ceiling and min debt per cdp.
+        DebtLimits(Balance, Balance),
+        /// Liquidation penalty in basis points of the covered debt, flat keeper tip
+        /// and keeper chip in basis points of the covered debt.
+        Penalty(u32, Balance, u32),
+        /// Annual stability fee in basis points.
+        StabilityFee(u32),
+        /// Auction starting price buffer, decay curve and max duration.
+        Auction(u32, DecayCurve, BlockNumber),
+        /// Oracle quorum and price ttl.
+        Oracle(u32, Timestamp),
+        /// Min time between an emergency shutdown and settlement.
+        ShutdownWait(Timestamp),
+        /// Delay between queueing and executing a param change.
+        GovDelay(Timestamp),
+        /// Max dai per flash mint and flash mint fee in basis points.
+        FlashMint(Balance, u32),
+        /// Savings contract allowed to draw interest from the surplus, `None` disables it.
+        Savings(Option<AccountId>),
+        /// Whitelist a price reporter.
+        AddReporter(AccountId),
+        /// Remove a price reporter and drop its quotes.
+        RemoveReporter(AccountId),
+        /// Register an erc20 collateral type with its min collateral ratio, min
+        /// liquidation ratio and debt ceiling.
+        AddIlk(AccountId, u32, u32, Balance),
+    }
+
+    #[derive(
+        Debug, PartialEq, Eq, Clone, Copy, scale::Encode, scale::Decode, SpreadLayout, PackedLayout,
+    )]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub struct Proposal {
+        pub change: ParamChange,
+        // earliest time the change can be executed
+        pub eta: Timestamp,
     }
 
     #[derive(
@@ -80,21 +523,166 @@ mod patramaker {
     )]
     pub struct CDP {
         pub issuer: AccountId,
-        pub collateral_dot: Balance,
+        pub ilk_id: IlkId,
+        pub collateral: Balance,
         // 1 DAI = 1 USD
         pub issue_dai: Balance,
         pub create_date: Timestamp,
+        // fee rate accumulator when issue_dai was last updated
+        pub rate: u128,
+        // auction selling the collateral of the cdp if one is running
+        pub auction: Option<AuctionId>,
+    }
+
+    /// A registered collateral type with its own price feed and risk params.
+    #[derive(
+        Debug, PartialEq, Eq, Clone, scale::Encode, scale::Decode, SpreadLayout, PackedLayout,
+    )]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub struct Ilk {
+        // erc20 collateral token, `None` for native dot
+        pub token: Option<AccountId>,
+        // smallest collateral units per whole token
+        pub unit: Balance,
+        // median price in USD with DOT_PRICE_DECIMALS
+        pub price: USD,
+        pub price_updated: Timestamp,
+        pub min_collateral_ratio: u32,
+        pub min_liquidation_ratio: u32,
+        pub debt_ceiling: Balance,
+        pub total_collateral: Balance,
+        pub total_debt: Balance,
+        // collateral set aside to back dai after an emergency shutdown
+        pub settle_collateral: Balance,
+    }
+
+    /// An emergency shutdown moves the system from Live to Caged, once every cdp is
+    /// skimmed it is Settled and dai can be redeemed for collateral.
+    #[derive(
+        Debug, PartialEq, Eq, Clone, Copy, scale::Encode, scale::Decode, SpreadLayout, PackedLayout,
+    )]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub enum SystemStatus {
+        Live,
+        Caged,
+        Settled,
+    }
+
+    /// How the auction price decays from its starting price.
+    #[derive(
+        Debug, PartialEq, Eq, Clone, Copy, scale::Encode, scale::Decode, SpreadLayout, PackedLayout,
+    )]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub enum DecayCurve {
+        /// Price falls linearly to zero over the given number of blocks.
+        Linear(BlockNumber),
+        /// Price is multiplied by `cut` percent every `step` blocks.
+        StairstepExponential(BlockNumber, u32),
+    }
+
+    #[derive(
+        Debug, PartialEq, Eq, Clone, scale::Encode, scale::Decode, SpreadLayout, PackedLayout,
+    )]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub struct Auction {
+        pub cdp_id: CdpId,
+        pub ilk_id: IlkId,
+        // collateral left for sale
+        pub collateral: Balance,
+        // dai left to raise
+        pub dai: Balance,
+        // starting price in USD with DOT_PRICE_DECIMALS
+        pub top: u128,
+        pub start_block: BlockNumber,
+    }
+
+    #[derive(
+        Debug, PartialEq, Eq, Clone, Copy, scale::Encode, scale::Decode, SpreadLayout, PackedLayout,
+    )]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub struct PriceQuote {
+        pub price: USD,
+        pub timestamp: Timestamp,
     }
 
     #[ink(storage)]
     pub struct PatraMaker {
         dai_token: Lazy<Erc20>,
         cdps: StorageMap<CdpId, CDP>,
+        // number of open cdps
         cdp_count: u32,
-        min_collateral_ratio: u32,
-        min_liquidation_ratio: u32,
-        liquidater_reward_ratio: u32,
-        dot_price: USD,
+        // last assigned cdp id
+        cdp_nonce: CdpId,
+        // account allowed to manage a cdp on behalf of its issuer
+        cdp_operators: StorageMap<CdpId, AccountId>,
+        // cdp ids of each owner by slot, one storage cell per cdp
+        owner_cdps: StorageMap<(AccountId, u32), CdpId>,
+        owner_cdp_count: StorageMap<AccountId, u32>,
+        // slot of each cdp in the index of its owner
+        cdp_slots: StorageMap<CdpId, u32>,
+        issuer_count: u32,
+        ilks: StorageMap<IlkId, Ilk>,
+        ilk_count: u32,
+        // total debt of all cdps
+        total_debt: Balance,
+        debt_ceiling: Balance,
+        // min debt of a cdp
+        dust: Balance,
+        // penalty on liquidated debt in basis points
+        liquidation_penalty: u32,
+        // flat dai part of the penalty paid to the keeper
+        keeper_tip: Balance,
+        // part of the penalty paid to the keeper in basis points of the covered debt
+        keeper_chip: u32,
+        reporters: Vec<AccountId>,
+        quotes: StorageMap<(IlkId, AccountId), PriceQuote>,
+        quorum: u32,
+        price_ttl: Timestamp,
+        stability_fee: u32,
+        debt_rate: u128,
+        rate_updated: Timestamp,
+        // stability fees, flash mint fees and liquidation penalties collected by the system
+        surplus: Balance,
+        // debt left without collateral after liquidations
+        bad_debt: Balance,
+        auctions: StorageMap<AuctionId, Auction>,
+        auction_count: u32,
+        auction_buf: u32,
+        auction_curve: DecayCurve,
+        auction_tail: BlockNumber,
+        live_auctions: u32,
+        status: SystemStatus,
+        caged_at: Timestamp,
+        shutdown_wait: Timestamp,
+        // dai supply when the redemption ratios were fixed
+        settle_dai: Balance,
+        redeemed_dai: Balance,
+        // queued param changes
+        proposals: StorageMap<ProposalId, Proposal>,
+        proposal_count: u32,
+        gov_delay: Timestamp,
+        // max dai per flash mint
+        flash_cap: Balance,
+        // flash mint fee in basis points
+        flash_fee: u32,
+        flash_lock: bool,
+        // savings contract paying its interest out of the surplus
+        savings: Option<AccountId>,
         owner: AccountId,
     }
 
@@ -121,240 +709,1940 @@ mod patramaker {
 
     impl PatraMaker {
         #[ink(constructor)]
-        pub fn new(dai_contract: AccountId) -> Self {
+        pub fn new(
+            dai_contract: AccountId,
+            gov_delay: Timestamp,
+            reporters: Vec<AccountId>,
+        ) -> Self {
             assert_ne!(dai_contract, Default::default());
+            assert!(gov_delay >= MIN_GOV_DELAY && gov_delay <= MAX_GOV_DELAY);
+            let mut initial_reporters = Vec::new();
+            for reporter in reporters {
+                if !initial_reporters.contains(&reporter) {
+                    initial_reporters.push(reporter);
+                }
+            }
             let caller = Self::env().caller();
             let dai_token: Erc20 = FromAccountId::from_account_id(dai_contract);
+            let mut ilks = StorageMap::new();
+            ilks.insert(
+                DOT_ILK,
+                Ilk {
+                    token: None,
+                    unit: DOTS,
+                    price: 3500,
+                    price_updated: 0,
+                    min_collateral_ratio: 150,
+                    min_liquidation_ratio: 110,
+                    debt_ceiling: Balance::MAX,
+                    total_collateral: 0,
+                    total_debt: 0,
+                    settle_collateral: 0,
+                },
+            );
             Self {
                 dai_token: Lazy::new(dai_token),
                 cdps: StorageMap::new(),
                 cdp_count: 0,
-                min_collateral_ratio: 150,
-                min_liquidation_ratio: 110,
-                liquidater_reward_ratio: 5,
-                dot_price: 3500,
+                cdp_nonce: 0,
+                cdp_operators: StorageMap::new(),
+                owner_cdps: StorageMap::new(),
+                owner_cdp_count: StorageMap::new(),
+                cdp_slots: StorageMap::new(),
+                issuer_count: 0,
+                ilks,
+                ilk_count: 1,
+                total_debt: 0,
+                debt_ceiling: Balance::MAX,
+                dust: 0,
+                liquidation_penalty: 1000,
+                keeper_tip: 0,
+                keeper_chip: 500,
+                reporters: initial_reporters,
+                quotes: StorageMap::new(),
+                quorum: 1,
+                price_ttl: DEFAULT_PRICE_TTL,
+                stability_fee: 0,
+                debt_rate: RATE_ONE,
+                rate_updated: Self::env().block_timestamp(),
+                surplus: 0,
+                bad_debt: 0,
+                auctions: StorageMap::new(),
+                auction_count: 0,
+                auction_buf: 120,
+                auction_curve: DecayCurve::StairstepExponential(10, 99),
+                auction_tail: DEFAULT_AUCTION_TAIL,
+                live_auctions: 0,
+                status: SystemStatus::Live,
+                caged_at: 0,
+                shutdown_wait: DEFAULT_SHUTDOWN_WAIT,
+                settle_dai: 0,
+                redeemed_dai: 0,
+                proposals: StorageMap::new(),
+                proposal_count: 0,
+                gov_delay,
+                flash_cap: 0,
+                flash_fee: 0,
+                flash_lock: false,
+                savings: None,
                 owner: caller,
             }
         }
 
-        /// Adjust Min Collateral Ratio only admin
+        /// Queue a risk param change only admin, it can be executed once the
+        /// governance delay has passed
         #[ink(message)]
-        pub fn adjust_mcr(&mut self, mcr: u32) {
-            self.only_owner();
-            self.min_collateral_ratio = mcr;
+        pub fn queue_change(&mut self, change: ParamChange) -> Result<ProposalId> {
+            self.only_owner()?;
+            if self.status != SystemStatus::Live {
+                return Err(Error::NotLive);
+            }
+            self.check_change(&change)?;
+            let eta = self.env().block_timestamp() + self.gov_delay;
+            self.proposal_count += 1;
+            let proposal_id = self.proposal_count;
+            self.proposals.insert(proposal_id, Proposal { change, eta });
+            self.env().emit_event(QueueChange {
+                proposal_id,
+                change,
+                eta,
+            });
+            Ok(proposal_id)
         }
 
-        // Adjust Min Liquidation Ratio only admin
+        /// Cancel a queued param change only admin
         #[ink(message)]
-        pub fn adjust_mlr(&mut self, mlr: u32) {
-            self.only_owner();
-            self.min_liquidation_ratio = mlr;
+        pub fn cancel_change(&mut self, proposal_id: ProposalId) -> Result<()> {
+            self.only_owner()?;
+            self.proposals
+                .take(&proposal_id)
+                .ok_or(Error::ProposalNotFound)?;
+            self.env().emit_event(CancelChange { proposal_id });
+            Ok(())
         }
 
-        /// Adjust Liquidater Reward Ratio only admin
+        /// Anyone can execute a queued param change after its eta and before the
+        /// grace period ends, bounds are checked again against the current params
         #[ink(message)]
-        pub fn adjust_lrr(&mut self, lrr: u32) {
-            self.only_owner();
-            self.liquidater_reward_ratio = lrr;
+        pub fn execute_change(&mut self, proposal_id: ProposalId) -> Result<()> {
+            if self.status != SystemStatus::Live {
+                return Err(Error::NotLive);
+            }
+            let proposal = self
+                .proposals
+                .get(&proposal_id)
+                .copied()
+                .ok_or(Error::ProposalNotFound)?;
+            let now = self.env().block_timestamp();
+            if now < proposal.eta {
+                return Err(Error::ProposalNotReady);
+            }
+            if now > proposal.eta.saturating_add(GOV_GRACE_PERIOD) {
+                return Err(Error::ProposalExpired);
+            }
+            self.check_change(&proposal.change)?;
+
+            self.proposals.take(&proposal_id);
+            self.apply_change(proposal.change);
+            self.env().emit_event(ExecuteChange {
+                proposal_id,
+                change: proposal.change,
+            });
+            Ok(())
         }
 
-        /// Adjust dot price only admin
+        /// Query queued param change by id
         #[ink(message)]
-        pub fn adjust_dot_price(&mut self, price: USD) {
-            self.only_owner();
-            self.dot_price = price;
+        pub fn query_proposal(&self, proposal_id: ProposalId) -> Option<Proposal> {
+            self.proposals.get(&proposal_id).copied()
         }
 
-        /// System params
+        /// Returns the total queued param change amount.
         #[ink(message)]
-        pub fn system_params(&self) -> (u32, u32, u32, u32) {
-            (
-                self.min_collateral_ratio,
-                self.min_liquidation_ratio,
-                self.liquidater_reward_ratio,
-                self.dot_price,
-            )
+        pub fn proposal_count(&self) -> u32 {
+            self.proposal_count
         }
 
-        /// Query cdp by id
+        /// Returns the delay between queueing and executing a param change.
         #[ink(message)]
-        pub fn query_cdp(&self, cdp_id: CdpId) -> Option<CDP> {
-            self.cdps.get(&cdp_id).cloned().and_then(|cdp| Some(cdp))
+        pub fn gov_delay(&self) -> Timestamp {
+            self.gov_delay
         }
 
-        /// Stake collateral and issue dai
-        #[ink(message, payable)]
-        pub fn issue_dai(&mut self, cr: u32) -> (CdpId, Balance) {
-            assert!(cr >= self.min_collateral_ratio);
+        /// Only reporter can submit a price quote of collateral type, the price is
+        /// updated to the median of all fresh quotes once the quorum is reached
+        #[ink(message)]
+        pub fn submit_price(&mut self, ilk_id: IlkId, price: USD) -> Result<()> {
+            if self.status != SystemStatus::Live {
+                return Err(Error::NotLive);
+            }
             let caller = self.env().caller();
-            let collateral = self.env().transferred_balance();
-            let dai_decimals =
-                10u128.saturating_pow(self.dai_token.token_decimals().unwrap() as u32);
-            let dai = collateral * self.dot_price as u128 * (dai_decimals / DOTS) * 100
-                / (cr * DOT_PRICE_DECIMALS) as u128;
-            let cdp = CDP {
-                issuer: caller,
-                collateral_dot: collateral,
-                issue_dai: dai,
-                create_date: self.env().block_timestamp(),
+            if !self.reporters.contains(&caller) {
+                return Err(Error::Unauthorized);
+            }
+            if !self.ilks.contains_key(&ilk_id) {
+                return Err(Error::IlkNotFound);
+            }
+            if price == 0 {
+                return Err(Error::InvalidPrice);
+            }
+            let now = self.env().block_timestamp();
+            self.quotes.insert(
+                (ilk_id, caller),
+                PriceQuote {
+                    price,
+                    timestamp: now,
+                },
+            );
+            self.env().emit_event(SubmitPrice {
+                ilk_id,
+                reporter: caller,
+                price,
+            });
+
+            let mut prices: Vec<USD> = self
+                .reporters
+                .iter()
+                .filter_map(|r| self.quotes.get(&(ilk_id, *r)))
+                .filter(|q| now.saturating_sub(q.timestamp) <= self.price_ttl)
+                .map(|q| q.price)
+                .collect();
+            if (prices.len() as u32) < self.quorum {
+                return Ok(());
+            }
+            prices.sort_unstable();
+            let mid = prices.len() / 2;
+            let median = if prices.len() % 2 == 0 {
+                ((prices[mid - 1] as u64 + prices[mid] as u64) / 2) as USD
+            } else {
+                prices[mid]
             };
-            self.cdp_count += 1;
-            self.cdps.insert(self.cdp_count, cdp);
-            self.dai_token.mint(caller, dai).unwrap();
-            self.env().emit_event(IssueDAI {
-                cdp_id: self.cdp_count,
-                collateral,
+            let ilk = self.ilks.get_mut(&ilk_id).unwrap();
+            ilk.price = median;
+            ilk.price_updated = now;
+            self.env().emit_event(UpdatePrice {
+                ilk_id,
+                price: median,
+                quotes: prices.len() as u32,
+            });
+            Ok(())
+        }
+
+        /// Returns the whitelisted price reporters.
+        #[ink(message)]
+        pub fn reporters(&self) -> Vec<AccountId> {
+            self.reporters.clone()
+        }
+
+        /// Returns the latest quote of reporter for collateral type.
+        #[ink(message)]
+        pub fn quote_of(&self, ilk_id: IlkId, reporter: AccountId) -> Option<PriceQuote> {
+            self.quotes.get(&(ilk_id, reporter)).copied()
+        }
+
+        /// Returns the quorum and the price ttl.
+        #[ink(message)]
+        pub fn oracle_params(&self) -> (u32, Timestamp) {
+            (self.quorum, self.price_ttl)
+        }
+
+        /// Returns the median price of collateral type, when it was updated and whether
+        /// it is still fresh.
+        #[ink(message)]
+        pub fn price_feed(&self, ilk_id: IlkId) -> Option<(USD, Timestamp, bool)> {
+            self.ilks
+                .get(&ilk_id)
+                .map(|ilk| (ilk.price, ilk.price_updated, self.price_is_fresh(ilk)))
+        }
+
+        /// System params of the native dot collateral type, global debt ceiling and dust
+        #[ink(message)]
+        pub fn system_params(&self) -> (u32, u32, u32, u32, Balance, Balance) {
+            let ilk = self.ilks.get(&DOT_ILK).unwrap();
+            (
+                ilk.min_collateral_ratio,
+                ilk.min_liquidation_ratio,
+                self.liquidation_penalty,
+                ilk.price,
+                self.debt_ceiling,
+                self.dust,
+            )
+        }
+
+        /// Returns the liquidation penalty and keeper chip in basis points of the covered
+        /// debt and the flat keeper tip.
+        #[ink(message)]
+        pub fn penalty_params(&self) -> (u32, Balance, u32) {
+            (self.liquidation_penalty, self.keeper_tip, self.keeper_chip)
+        }
+
+        /// Returns the total debt of all cdps.
+        #[ink(message)]
+        pub fn total_debt(&self) -> Balance {
+            self.total_debt
+        }
+
+        /// Query collateral type by id
+        #[ink(message)]
+        pub fn query_ilk(&self, ilk_id: IlkId) -> Option<Ilk> {
+            self.ilks.get(&ilk_id).cloned()
+        }
+
+        /// Returns the total collateral type amount.
+        #[ink(message)]
+        pub fn ilk_count(&self) -> u32 {
+            self.ilk_count
+        }
+
+        /// Returns the annual stability fee, the current fee rate accumulator.
+        #[ink(message)]
+        pub fn stability_params(&self) -> (u32, u128) {
+            (self.stability_fee, self.current_rate())
+        }
+
+        /// Returns the stability fees, flash mint fees and liquidation penalties collected by
+        /// the system, less the bad debt healed and the savings interest paid out of it.
+        #[ink(message)]
+        pub fn surplus(&self) -> Balance {
+            self.surplus
+        }
+
+        /// Returns the debt written off without collateral to back it.
+        #[ink(message)]
+        pub fn bad_debt(&self) -> Balance {
+            self.bad_debt
+        }
+
+        /// Returns the surplus, the bad debt and the total debt of all cdps.
+        #[ink(message)]
+        pub fn solvency(&self) -> (Balance, Balance, Balance) {
+            (self.surplus, self.bad_debt, self.total_debt)
+        }
+
+        /// Anyone can net the bad debt against the surplus, returns the dai netted.
+        #[ink(message)]
+        pub fn heal(&mut self) -> Result<Balance> {
+            let dai = self.surplus.min(self.bad_debt);
+            if dai > 0 {
+                self.surplus -= dai;
+                self.bad_debt -= dai;
+                self.env().emit_event(Heal { dai });
+            }
+            Ok(dai)
+        }
+
+        /// Returns the surplus net of bad debt, the most savings interest can draw.
+        #[ink(message)]
+        pub fn available_surplus(&self) -> Balance {
+            self.surplus.saturating_sub(self.bad_debt)
+        }
+
+        /// Returns the savings contract paying its interest out of the surplus.
+        #[ink(message)]
+        pub fn savings(&self) -> Option<AccountId> {
+            self.savings
+        }
+
+        /// Only the savings contract can mint dai to itself out of the surplus net of
+        /// bad debt to pay the savings interest
+        #[ink(message)]
+        pub fn pay_interest(&mut self, dai: Balance) -> Result<()> {
+            if self.status != SystemStatus::Live {
+                return Err(Error::NotLive);
+            }
+            let caller = self.env().caller();
+            if self.savings != Some(caller) {
+                return Err(Error::Unauthorized);
+            }
+            if dai > self.available_surplus() {
+                return Err(Error::InsufficientSurplus);
+            }
+
+            self.dai_token
+                .mint(caller, dai)
+                .map_err(|_| Error::DaiMintFailed)?;
+            self.surplus -= dai;
+            self.env().emit_event(PayInterest {
+                savings: caller,
                 dai,
             });
-            (self.cdp_count, dai)
+            Ok(())
+        }
+
+        /// Returns the max dai per flash mint and the flash mint fee in basis points.
+        #[ink(message)]
+        pub fn flash_params(&self) -> (Balance, u32) {
+            (self.flash_cap, self.flash_fee)
+        }
+
+        /// Query cdp by id, issue_dai is the current outstanding debt including fees
+        #[ink(message)]
+        pub fn query_cdp(&self, cdp_id: CdpId) -> Option<CDP> {
+            let rate = self.current_rate();
+            self.cdps
+                .get(&cdp_id)
+                .cloned()
+                .map(|cdp| Self::with_rate(cdp, rate))
+        }
+
+        /// Returns the current collateral ratio of cdp in percent.
+        #[ink(message)]
+        pub fn collateral_ratio(&self, cdp_id: CdpId) -> Option<u32> {
+            self.cdp_with_ilk(cdp_id).and_then(|(cdp, ilk)| {
+                self.calc_collateral_ratio(&ilk, cdp.collateral, cdp.issue_dai)
+                    .ok()
+            })
+        }
+
+        /// Returns the price at or below which cdp can be liquidated, in USD with
+        /// DOT_PRICE_DECIMALS.
+        #[ink(message)]
+        pub fn liquidation_price(&self, cdp_id: CdpId) -> Option<USD> {
+            self.cdp_with_ilk(cdp_id).and_then(|(cdp, ilk)| {
+                if cdp.issue_dai == 0 {
+                    return Some(0);
+                }
+                if cdp.collateral == 0 {
+                    return Some(USD::MAX);
+                }
+                let decimals = self.dai_decimals().ok()?;
+                let price = math::mul_div_wide(
+                    U256::from(cdp.issue_dai) * U256::from(ilk.min_liquidation_ratio),
+                    U256::from(ilk.unit) * U256::from(DOT_PRICE_DECIMALS),
+                    U256::from(cdp.collateral) * U256::from(100) * U256::from(decimals),
+                    Rounding::Down,
+                )
+                .filter(|price| *price <= U256::from(USD::MAX))
+                .map_or(USD::MAX, |price| price.as_u32());
+                Some(price)
+            })
+        }
+
+        /// Returns the collateral that can be withdrawn from cdp without falling below
+        /// the min collateral ratio.
+        #[ink(message)]
+        pub fn max_withdrawable(&self, cdp_id: CdpId) -> Option<Balance> {
+            self.cdp_with_ilk(cdp_id).and_then(|(cdp, ilk)| {
+                let locked = self
+                    .min_collateral(&ilk, cdp.issue_dai, ilk.min_collateral_ratio)
+                    .ok()?;
+                Some(cdp.collateral.saturating_sub(locked))
+            })
+        }
+
+        /// Returns the dai that can still be drawn from cdp without falling below the
+        /// min collateral ratio or exceeding the debt ceilings.
+        #[ink(message)]
+        pub fn max_drawable(&self, cdp_id: CdpId) -> Option<Balance> {
+            self.cdp_with_ilk(cdp_id).and_then(|(cdp, ilk)| {
+                let max = match self.max_debt(&ilk, cdp.collateral, ilk.min_collateral_ratio) {
+                    Err(Error::Overflow) => Balance::MAX,
+                    max => max.ok()?,
+                };
+                Some(
+                    max.saturating_sub(cdp.issue_dai)
+                        .min(ilk.debt_ceiling.saturating_sub(ilk.total_debt))
+                        .min(self.debt_ceiling.saturating_sub(self.total_debt)),
+                )
+            })
+        }
+
+        /// Returns up to `limit` ids of cdps with id from `start` that are at or below
+        /// the min liquidation ratio.
+        #[ink(message)]
+        pub fn unsafe_cdps(&self, start: CdpId, limit: u32) -> Vec<CdpId> {
+            let end = start
+                .saturating_add(limit.min(MAX_PAGE_SIZE))
+                .min(self.cdp_nonce.saturating_add(1));
+            (start.max(1)..end)
+                .filter(|id| {
+                    self.cdp_with_ilk(*id).map_or(false, |(cdp, ilk)| {
+                        cdp.issue_dai > 0
+                            && self
+                                .calc_collateral_ratio(&ilk, cdp.collateral, cdp.issue_dai)
+                                .map_or(false, |cr| cr <= ilk.min_liquidation_ratio)
+                    })
+                })
+                .collect()
+        }
+
+        /// Returns the number of cdps owned by `owner`.
+        #[ink(message)]
+        pub fn cdp_count_of(&self, owner: AccountId) -> u32 {
+            self.owner_cdp_count.get(&owner).copied().unwrap_or(0)
         }
 
-        /// Only issuer can add collateral and update collateral ratio
+        /// Returns up to `limit` ids of cdps owned by `owner` from the `start`th, the
+        /// order changes as cdps are closed or transferred
+        #[ink(message)]
+        pub fn cdps_of(&self, owner: AccountId, start: u32, limit: u32) -> Vec<CdpId> {
+            let end = start
+                .saturating_add(limit.min(MAX_PAGE_SIZE))
+                .min(self.cdp_count_of(owner));
+            (start..end)
+                .filter_map(|slot| self.owner_cdps.get(&(owner, slot)).copied())
+                .collect()
+        }
+
+        /// Returns up to `limit` cdps with id from `start`, issue_dai is the current
+        /// outstanding debt including fees
+        #[ink(message)]
+        pub fn list_cdps(&self, start: CdpId, limit: u32) -> Vec<(CdpId, CDP)> {
+            let rate = self.current_rate();
+            let end = start
+                .saturating_add(limit.min(MAX_PAGE_SIZE))
+                .min(self.cdp_nonce.saturating_add(1));
+            (start.max(1)..end)
+                .filter_map(|id| {
+                    self.cdps
+                        .get(&id)
+                        .cloned()
+                        .map(|cdp| (id, Self::with_rate(cdp, rate)))
+                })
+                .collect()
+        }
+
+        /// Stake collateral of collateral type and issue dai, for the native dot
+        /// collateral type `collateral` must equal the transferred value. Traps on
+        /// failure if any value was transferred so it is refunded
         #[ink(message, payable)]
-        pub fn add_collateral(&mut self, cdp_id: CdpId) {
-            assert!(self.cdps.contains_key(&cdp_id));
+        pub fn issue_dai(
+            &mut self,
+            ilk_id: IlkId,
+            collateral: Balance,
+            cr: u32,
+        ) -> Result<(CdpId, Balance)> {
+            let result = self.open_cdp(ilk_id, collateral, cr);
+            self.revert_if_paid(result)
+        }
+
+        /// Only issuer or operator can add collateral and update collateral ratio, for the native
+        /// dot collateral type `collateral` must equal the transferred value. Traps on failure
+        /// if any value was transferred so it is refunded
+        #[ink(message, payable)]
+        pub fn add_collateral(&mut self, cdp_id: CdpId, collateral: Balance) -> Result<()> {
+            let result = self.lock_collateral(cdp_id, collateral);
+            self.revert_if_paid(result)
+        }
+
+        /// Only issuer or operator can minus collateral to the issuer, refused if the cdp
+        /// would fall below the min collateral ratio
+        #[ink(message)]
+        pub fn minus_collateral(&mut self, cdp_id: CdpId, collateral: Balance) -> Result<()> {
+            if self.status != SystemStatus::Live {
+                return Err(Error::NotLive);
+            }
             let caller = self.env().caller();
-            let collateral = self.env().transferred_balance();
-            let cdp = self.cdps.get_mut(&cdp_id).unwrap();
-            assert!(cdp.issuer == caller);
-            // let cr = (collateral + cdp.collateral_dot as u128) * self.dot_price as u128 * 100
-            //     / cdp.issue_dai;
-            let dai_decimals =
-                10u128.saturating_pow(self.dai_token.token_decimals().unwrap() as u32);
-            let cr = (collateral + cdp.collateral_dot as u128)
-                * self.dot_price as u128
-                * 100
-                * dai_decimals
-                / (cdp.issue_dai * DOTS * DOT_PRICE_DECIMALS as u128);
-
-            // assert!(cr >= self.min_collateral_ratio.into());
-            cdp.collateral_dot += collateral;
-            self.env().emit_event(AddCollateral {
+            let cdp = self.cdps.get(&cdp_id).cloned().ok_or(Error::CdpNotFound)?;
+            if !self.can_manage(cdp_id, caller) {
+                return Err(Error::NotIssuer);
+            }
+            if collateral > cdp.collateral {
+                return Err(Error::InsufficientCollateral);
+            }
+            let ilk = self
+                .ilks
+                .get(&cdp.ilk_id)
+                .cloned()
+                .ok_or(Error::IlkNotFound)?;
+            let debt = Self::accrue(cdp.issue_dai, cdp.rate, self.current_rate());
+            if debt > 0 && !self.price_is_fresh(&ilk) {
+                return Err(Error::StalePrice);
+            }
+            let cr = self.calc_collateral_ratio(&ilk, cdp.collateral - collateral, debt)?;
+            if cr < ilk.min_collateral_ratio {
+                return Err(Error::BelowMinCollateralRatio);
+            }
+
+            self.push_collateral(&ilk, cdp.issuer, collateral)?;
+            self.cdps.get_mut(&cdp_id).unwrap().collateral -= collateral;
+            self.sub_from_ilk(cdp.ilk_id, collateral, 0);
+            self.env().emit_event(MinusCollateral {
                 cdp_id,
-                add_collateral: collateral,
-                collateral_ratio: cr as u32,
+                minus_collateral: collateral,
+                collateral_ratio: cr,
             });
+            Ok(())
         }
 
-        /// Only issuer can minus collateral and update collateral ratio
+        /// Only issuer or operator can draw more dai to the issuer against an
+        /// over-collateralised cdp, returns the outstanding debt
         #[ink(message)]
-        pub fn minus_collateral(&mut self, cdp_id: CdpId, collateral: Balance) {
-            assert!(self.cdps.contains_key(&cdp_id));
+        pub fn draw_dai(&mut self, cdp_id: CdpId, dai: Balance) -> Result<Balance> {
+            if self.status != SystemStatus::Live {
+                return Err(Error::NotLive);
+            }
             let caller = self.env().caller();
-            let cdp = self.cdps.get_mut(&cdp_id).unwrap();
-            assert!(cdp.issuer == caller);
-            // let cr =
-            //     (cdp.collateral_dot - collateral) * self.dot_price as u128 * 100 / cdp.issue_dai;
-            let dai_decimals =
-                10u128.saturating_pow(self.dai_token.token_decimals().unwrap() as u32);
-            let cr =
-                (cdp.collateral_dot - collateral) * self.dot_price as u128 * 100 * dai_decimals
-                    / (cdp.issue_dai * DOTS * DOT_PRICE_DECIMALS as u128);
-
-            // assert!(cr >= self.min_collateral_ratio.into());
-            cdp.collateral_dot -= collateral;
-            self.env().transfer(caller, collateral).unwrap();
-            self.env().emit_event(MinusCollateral {
+            if !self.cdps.contains_key(&cdp_id) {
+                return Err(Error::CdpNotFound);
+            }
+            if !self.can_manage(cdp_id, caller) {
+                return Err(Error::NotIssuer);
+            }
+            // fees due so far are charged first, the checks below see the current debt
+            self.drip();
+            self.charge_fee(cdp_id)?;
+            let cdp = self.cdps.get(&cdp_id).cloned().unwrap();
+            let ilk = self
+                .ilks
+                .get(&cdp.ilk_id)
+                .cloned()
+                .ok_or(Error::IlkNotFound)?;
+            if !self.price_is_fresh(&ilk) {
+                return Err(Error::StalePrice);
+            }
+            if dai > ilk.debt_ceiling.saturating_sub(ilk.total_debt)
+                || dai > self.debt_ceiling.saturating_sub(self.total_debt)
+            {
+                return Err(Error::DebtCeilingExceeded);
+            }
+            let debt = cdp.issue_dai.checked_add(dai).ok_or(Error::Overflow)?;
+            if debt < self.dust {
+                return Err(Error::DustDebt);
+            }
+            let cr = self.calc_collateral_ratio(&ilk, cdp.collateral, debt)?;
+            if cr < ilk.min_collateral_ratio {
+                return Err(Error::BelowMinCollateralRatio);
+            }
+
+            self.dai_token
+                .mint(cdp.issuer, dai)
+                .map_err(|_| Error::DaiMintFailed)?;
+            self.cdps.get_mut(&cdp_id).unwrap().issue_dai = debt;
+            assert!(self.add_to_ilk(cdp.ilk_id, 0, dai).is_ok());
+            self.env().emit_event(DrawDAI {
                 cdp_id,
-                minus_collateral: collateral,
-                collateral_ratio: cr as u32,
+                dai,
+                collateral_ratio: cr,
             });
+            Ok(debt)
         }
 
-        /// Only issuer can withdraw
+        /// Only issuer or operator can withdraw, the caller repays the dai and the collateral
+        /// is sent to the issuer, accrued stability fee is charged first
         #[ink(message)]
-        pub fn withdraw_dot(&mut self, cdp_id: CdpId, dai: Balance) -> Balance {
-            assert!(self.cdps.contains_key(&cdp_id));
+        pub fn withdraw_dot(&mut self, cdp_id: CdpId, dai: Balance) -> Result<Balance> {
+            if self.status != SystemStatus::Live {
+                return Err(Error::NotLive);
+            }
             let caller = self.env().caller();
-            let cdp = self.cdps.get_mut(&cdp_id).unwrap();
-            assert!(cdp.issuer == caller);
-            // let cr = (cdp.collateral_dot * self.dot_price as u128 * 100 / cdp.issue_dai) as u32;
-            // assert!(cr >= self.min_collateral_ratio);
-            assert!(dai <= cdp.issue_dai);
-
-            let bt: U256 = cdp.collateral_dot.into();
-            let bi: U256 = dai.into();
-            let ui: U256 = cdp.issue_dai.into();
-            let r = bt * bi / ui;
-            let dot = r.as_u128();
-
-            cdp.collateral_dot -= dot;
-            cdp.issue_dai -= dai;
-            self.env().transfer(caller, dot).unwrap();
-            self.dai_token.burn(caller, dai).unwrap();
+            let cdp = self.cdps.get(&cdp_id).cloned().ok_or(Error::CdpNotFound)?;
+            if !self.can_manage(cdp_id, caller) {
+                return Err(Error::NotIssuer);
+            }
+            let ilk = self
+                .ilks
+                .get(&cdp.ilk_id)
+                .cloned()
+                .ok_or(Error::IlkNotFound)?;
+            let debt = Self::accrue(cdp.issue_dai, cdp.rate, self.current_rate());
+            if dai > debt {
+                return Err(Error::RepayExceedsDebt);
+            }
+            if dai < debt && debt - dai < self.dust {
+                return Err(Error::DustDebt);
+            }
+            // collateral released pro rata rounds down
+            let dot = if dai == debt {
+                cdp.collateral
+            } else {
+                math::mul_div(cdp.collateral, dai, debt, Rounding::Down).ok_or(Error::Overflow)?
+            };
+
+            self.drip();
+            self.charge_fee(cdp_id)?;
+            self.dai_token
+                .burn(caller, dai)
+                .map_err(|_| Error::DaiBurnFailed)?;
+            let cdp_mut = self.cdps.get_mut(&cdp_id).unwrap();
+            cdp_mut.collateral -= dot;
+            cdp_mut.issue_dai -= dai;
+            self.sub_from_ilk(cdp.ilk_id, dot, dai);
+            assert!(self.push_collateral(&ilk, cdp.issuer, dot).is_ok());
             self.env().emit_event(Withdraw {
                 cdp_id,
                 collateral: dot,
                 dai,
             });
-            dot
+            Ok(dot)
+        }
+
+        /// Only issuer or operator can repay dai without withdrawing collateral,
+        /// returns the outstanding debt
+        #[ink(message)]
+        pub fn repay_dai(&mut self, cdp_id: CdpId, dai: Balance) -> Result<Balance> {
+            if self.status != SystemStatus::Live {
+                return Err(Error::NotLive);
+            }
+            let caller = self.env().caller();
+            let cdp = self.cdps.get(&cdp_id).cloned().ok_or(Error::CdpNotFound)?;
+            if !self.can_manage(cdp_id, caller) {
+                return Err(Error::NotIssuer);
+            }
+            let debt = Self::accrue(cdp.issue_dai, cdp.rate, self.current_rate());
+            if dai > debt {
+                return Err(Error::RepayExceedsDebt);
+            }
+            if dai < debt && debt - dai < self.dust {
+                return Err(Error::DustDebt);
+            }
+
+            self.drip();
+            self.charge_fee(cdp_id)?;
+            self.dai_token
+                .burn(caller, dai)
+                .map_err(|_| Error::DaiBurnFailed)?;
+            self.cdps.get_mut(&cdp_id).unwrap().issue_dai -= dai;
+            self.sub_from_ilk(cdp.ilk_id, 0, dai);
+            self.env().emit_event(RepayDAI { cdp_id, dai });
+            Ok(debt - dai)
+        }
+
+        /// Only issuer or operator can close a cdp, its outstanding debt is burned from the
+        /// caller, all collateral is returned to the issuer and the cdp is removed. Returns
+        /// the collateral
+        #[ink(message)]
+        pub fn close_cdp(&mut self, cdp_id: CdpId) -> Result<Balance> {
+            if self.status != SystemStatus::Live {
+                return Err(Error::NotLive);
+            }
+            let caller = self.env().caller();
+            let cdp = self.cdps.get(&cdp_id).cloned().ok_or(Error::CdpNotFound)?;
+            if !self.can_manage(cdp_id, caller) {
+                return Err(Error::NotIssuer);
+            }
+            if cdp.auction.is_some() {
+                return Err(Error::AuctionRunning);
+            }
+            let ilk = self
+                .ilks
+                .get(&cdp.ilk_id)
+                .cloned()
+                .ok_or(Error::IlkNotFound)?;
+            let debt = Self::accrue(cdp.issue_dai, cdp.rate, self.current_rate());
+
+            self.drip();
+            self.charge_fee(cdp_id)?;
+            if debt > 0 {
+                self.dai_token
+                    .burn(caller, debt)
+                    .map_err(|_| Error::DaiBurnFailed)?;
+            }
+            self.cdps.take(&cdp_id);
+            self.cdp_operators.take(&cdp_id);
+            self.unindex_cdp(cdp.issuer, cdp_id);
+            self.cdp_count -= 1;
+            self.sub_from_ilk(cdp.ilk_id, cdp.collateral, debt);
+            assert!(self
+                .push_collateral(&ilk, cdp.issuer, cdp.collateral)
+                .is_ok());
+            self.env().emit_event(CloseCDP {
+                cdp_id,
+                collateral: cdp.collateral,
+                dai: debt,
+            });
+            Ok(cdp.collateral)
+        }
+
+        /// Only issuer can transfer cdp to a new owner, the operator is revoked
+        #[ink(message)]
+        pub fn transfer_cdp(&mut self, cdp_id: CdpId, to: AccountId) -> Result<()> {
+            let caller = self.env().caller();
+            let cdp = self.cdps.get_mut(&cdp_id).ok_or(Error::CdpNotFound)?;
+            if cdp.issuer != caller {
+                return Err(Error::NotIssuer);
+            }
+            cdp.issuer = to;
+            self.cdp_operators.take(&cdp_id);
+            self.unindex_cdp(caller, cdp_id);
+            self.index_cdp(to, cdp_id);
+            self.env().emit_event(TransferCDP {
+                cdp_id,
+                from: caller,
+                to,
+            });
+            Ok(())
+        }
+
+        /// Only issuer can approve an operator to manage the cdp, `None` revokes it.
+        /// The operator can do everything but transfer the cdp or change its operator,
+        /// collateral and dai it takes out of the cdp are always sent to the issuer
+        #[ink(message)]
+        pub fn approve_cdp_operator(
+            &mut self,
+            cdp_id: CdpId,
+            operator: Option<AccountId>,
+        ) -> Result<()> {
+            let caller = self.env().caller();
+            let cdp = self.cdps.get(&cdp_id).ok_or(Error::CdpNotFound)?;
+            if cdp.issuer != caller {
+                return Err(Error::NotIssuer);
+            }
+            match operator {
+                Some(operator) => self.cdp_operators.insert(cdp_id, operator),
+                None => self.cdp_operators.take(&cdp_id),
+            };
+            self.env().emit_event(ApproveCDPOperator {
+                cdp_id,
+                owner: caller,
+                operator,
+            });
+            Ok(())
+        }
+
+        /// Returns the operator of cdp.
+        #[ink(message)]
+        pub fn cdp_operator(&self, cdp_id: CdpId) -> Option<AccountId> {
+            self.cdp_operators.get(&cdp_id).copied()
         }
 
         /// Anyone can invoke collateral liquidation if current collateral ratio lower than minimum
         #[ink(message)]
-        pub fn liquidate_collateral(&mut self, cdp_id: CdpId, dai: Balance) {
-            assert!(self.cdps.contains_key(&cdp_id));
-            let cdp = self.cdps.get_mut(&cdp_id).unwrap();
-            // let cr = (cdp.collateral_dot * self.dot_price as u128 * 100 / cdp.issue_dai) as u32;
-            let dai_decimals =
-                10u128.saturating_pow(self.dai_token.token_decimals().unwrap() as u32);
-            let cr = (cdp.collateral_dot * self.dot_price as u128 * 100 * dai_decimals
-                / (cdp.issue_dai * DOTS * DOT_PRICE_DECIMALS as u128)) as u32;
-            assert!(cr <= self.min_liquidation_ratio);
+        pub fn liquidate_collateral(&mut self, cdp_id: CdpId, dai: Balance) -> Result<()> {
+            if self.status != SystemStatus::Live {
+                return Err(Error::NotLive);
+            }
+            let mut cdp = self.cdps.get(&cdp_id).cloned().ok_or(Error::CdpNotFound)?;
+            let ilk_id = cdp.ilk_id;
+            let ilk = self.ilks.get(&ilk_id).cloned().ok_or(Error::IlkNotFound)?;
+            if !self.price_is_fresh(&ilk) {
+                return Err(Error::StalePrice);
+            }
+            let debt = Self::accrue(cdp.issue_dai, cdp.rate, self.current_rate());
+            let cr = self.calc_collateral_ratio(&ilk, cdp.collateral, debt)?;
+            if cr > ilk.min_liquidation_ratio {
+                return Err(Error::NotLiquidatable);
+            }
+            let dai = dai.min(debt);
+            // penalty owed to the system rounds up
+            let penalty = math::mul_div(
+                dai,
+                self.liquidation_penalty as u128,
+                FEE_DECIMALS as u128,
+                Rounding::Up,
+            )
+            .ok_or(Error::Overflow)?;
+            // the collateral backs the covered debt first, the penalty only as far as it reaches
+            let value = self.max_debt(&ilk, cdp.collateral, 100)?;
+            let penalty = penalty.min(value.saturating_sub(dai));
+            let chip = math::mul_div(
+                dai,
+                self.keeper_chip as u128,
+                FEE_DECIMALS as u128,
+                Rounding::Down,
+            )
+            .ok_or(Error::Overflow)?;
+            // the keeper is paid first, the rest of the penalty goes to the surplus
+            let keeper_tip = self.keeper_tip.saturating_add(chip).min(penalty);
+            let surplus = penalty - keeper_tip;
+            // collateral paid out to the keeper rounds down, if it does not cover the
+            // debt the keeper takes all of it
+            let seized = self
+                .collateral_for(&ilk, dai + penalty, Rounding::Down)?
+                .min(cdp.collateral);
+            let mut rest_debt = debt - dai;
+            // debt left without collateral is written off as bad debt
+            let bad_debt = if seized == cdp.collateral {
+                rest_debt
+            } else {
+                0
+            };
+            rest_debt -= bad_debt;
+            if rest_debt > 0 && rest_debt < self.dust {
+                return Err(Error::DustDebt);
+            }
+            if self.bad_debt.checked_add(bad_debt).is_none() {
+                return Err(Error::Overflow);
+            }
+
+            self.drip();
+            self.charge_fee(cdp_id)?;
+            let total_surplus = self.surplus.checked_add(surplus).ok_or(Error::Overflow)?;
+
+            let caller = self.env().caller();
+            // the keeper repays the covered debt and the surplus part of the penalty
+            self.dai_token
+                .burn(caller, dai + surplus)
+                .map_err(|_| Error::DaiBurnFailed)?;
+            self.surplus = total_surplus;
             let owner = cdp.issuer;
-            let dot =
-                dai * DOTS * DOT_PRICE_DECIMALS as u128 / (self.dot_price as u128 * dai_decimals);
-            cdp.issue_dai = cdp.issue_dai.saturating_sub(dai);
-            // let keeper_reward =
-            //     dai * DOTS * self.liquidater_reward_ratio as u128 * DOT_PRICE_DECIMALS as u128
-            //         / (100 * self.dot_price as u128 * dai_decimals);
-            let keeper_reward = dai * DOTS * self.liquidater_reward_ratio as u128
-                / (self.dot_price as u128 * dai_decimals);
-            assert!(dot + keeper_reward <= cdp.collateral_dot);
-
-            cdp.collateral_dot = cdp.collateral_dot - dot - keeper_reward;
+            cdp.issue_dai = rest_debt;
+            cdp.collateral -= seized;
+            cdp.rate = self.debt_rate;
             let mut rest_dot = 0_u128;
-            if cdp.issue_dai == 0 && cdp.collateral_dot > 0 {
-                rest_dot = cdp.collateral_dot;
-                cdp.collateral_dot = 0;
+            if cdp.issue_dai == 0 && cdp.collateral > 0 {
+                rest_dot = cdp.collateral;
+                cdp.collateral = 0;
             }
-            let caller = self.env().caller();
-            assert!(self.env().transfer(caller, dot + keeper_reward).is_ok());
-            assert!(self.dai_token.burn(caller, dai).is_ok());
+            self.cdps.insert(cdp_id, cdp);
+            self.sub_from_ilk(ilk_id, seized + rest_dot, debt - rest_debt);
+            assert!(self.record_bad_debt(cdp_id, bad_debt).is_ok());
+            assert!(self.push_collateral(&ilk, caller, seized).is_ok());
             if rest_dot > 0 {
-                assert!(self.env().transfer(owner, rest_dot).is_ok());
+                assert!(self.push_collateral(&ilk, owner, rest_dot).is_ok());
             }
             self.env().emit_event(Liquidate {
                 cdp_id,
-                collateral: dot,
-                keeper_reward,
+                collateral: seized,
+                dai,
+                keeper_tip,
+                surplus,
             });
+            Ok(())
         }
 
-        /// Returns the total issuers、total collateral、total issue dai.
+        /// Anyone can kick a cdp below the min liquidation ratio, its collateral and debt
+        /// are moved into a dutch auction
         #[ink(message)]
-        pub fn total_supply(&self) -> (u32, Balance, Balance) {
-            let mut issuers = Vec::new();
-            let total_collateral: Balance = self.env().balance();
-            let total_issue_dai: Balance = self.dai_token.total_supply();
-            for (_k, v) in self.cdps.iter() {
-                if !issuers.contains(&v.issuer) {
-                    issuers.push(v.issuer);
+        pub fn kick(&mut self, cdp_id: CdpId) -> Result<AuctionId> {
+            if self.status != SystemStatus::Live {
+                return Err(Error::NotLive);
+            }
+            let cdp = self.cdps.get(&cdp_id).cloned().ok_or(Error::CdpNotFound)?;
+            if cdp.auction.is_some() {
+                return Err(Error::AuctionRunning);
+            }
+            let ilk = self
+                .ilks
+                .get(&cdp.ilk_id)
+                .cloned()
+                .ok_or(Error::IlkNotFound)?;
+            if !self.price_is_fresh(&ilk) {
+                return Err(Error::StalePrice);
+            }
+            let debt = Self::accrue(cdp.issue_dai, cdp.rate, self.current_rate());
+            let cr = self.calc_collateral_ratio(&ilk, cdp.collateral, debt)?;
+            if debt == 0 || cr > ilk.min_liquidation_ratio {
+                return Err(Error::NotLiquidatable);
+            }
+
+            self.drip();
+            self.charge_fee(cdp_id)?;
+            let auction = Auction {
+                cdp_id,
+                ilk_id: cdp.ilk_id,
+                collateral: cdp.collateral,
+                dai: debt,
+                top: ilk.price as u128 * self.auction_buf as u128 / 100,
+                start_block: self.env().block_number(),
+            };
+            self.auction_count += 1;
+            self.live_auctions += 1;
+            let auction_id = self.auction_count;
+            self.auctions.insert(auction_id, auction);
+            let cdp_mut = self.cdps.get_mut(&cdp_id).unwrap();
+            cdp_mut.collateral = 0;
+            cdp_mut.issue_dai = 0;
+            cdp_mut.auction = Some(auction_id);
+            self.sub_from_ilk(cdp.ilk_id, cdp.collateral, debt);
+            self.env().emit_event(Kick {
+                auction_id,
+                cdp_id,
+                collateral: cdp.collateral,
+                dai: debt,
+            });
+            Ok(auction_id)
+        }
+
+        /// Buy up to `collateral` from an auction at its current price with dai,
+        /// returns the collateral bought and the dai paid
+        #[ink(message)]
+        pub fn take(
+            &mut self,
+            auction_id: AuctionId,
+            collateral: Balance,
+            max_price: u128,
+        ) -> Result<(Balance, Balance)> {
+            if self.status != SystemStatus::Live {
+                return Err(Error::NotLive);
+            }
+            let caller = self.env().caller();
+            let mut auction = self
+                .auctions
+                .get(&auction_id)
+                .cloned()
+                .ok_or(Error::AuctionNotFound)?;
+            let ilk = self
+                .ilks
+                .get(&auction.ilk_id)
+                .cloned()
+                .ok_or(Error::IlkNotFound)?;
+            let (price, expired) = self.auction_status(&auction);
+            if expired {
+                return Err(Error::AuctionExpired);
+            }
+            if price > max_price {
+                return Err(Error::PriceTooHigh);
+            }
+
+            let value = U256::from(price) * U256::from(self.dai_decimals()?);
+            let unit = U256::from(ilk.unit) * U256::from(DOT_PRICE_DECIMALS);
+            let mut slice = collateral.min(auction.collateral);
+            // buyer pays rounded up
+            let mut owe = math::mul_div_wide(slice.into(), value, unit, Rounding::Up)
+                .and_then(math::to_u128)
+                .ok_or(Error::Overflow)?;
+            if owe > auction.dai {
+                owe = auction.dai;
+                // buyer receives rounded down
+                slice = math::mul_div_wide(owe.into(), unit, value, Rounding::Down)
+                    .and_then(math::to_u128)
+                    .ok_or(Error::Overflow)?
+                    .min(auction.collateral);
+            }
+            if self.bad_debt.checked_add(auction.dai - owe).is_none() {
+                return Err(Error::Overflow);
+            }
+
+            self.dai_token
+                .burn(caller, owe)
+                .map_err(|_| Error::DaiBurnFailed)?;
+            assert!(self.push_collateral(&ilk, caller, slice).is_ok());
+            auction.collateral -= slice;
+            auction.dai -= owe;
+            self.env().emit_event(Take {
+                auction_id,
+                collateral: slice,
+                dai: owe,
+                price,
+            });
+
+            if auction.dai == 0 || auction.collateral == 0 {
+                self.auctions.take(&auction_id);
+                self.live_auctions -= 1;
+                let cdp = self.cdps.get_mut(&auction.cdp_id).unwrap();
+                cdp.auction = None;
+                // leftover collateral goes to whoever holds the cdp now
+                let issuer = cdp.issuer;
+                if auction.collateral > 0 {
+                    assert!(self
+                        .push_collateral(&ilk, issuer, auction.collateral)
+                        .is_ok());
                 }
-                // total_collateral += v.collateral_dot;
-                // total_issue_dai += v.issue_dai;
+                // dai the sold collateral could not raise is written off as bad debt
+                assert!(self.record_bad_debt(auction.cdp_id, auction.dai).is_ok());
+                self.env().emit_event(AuctionEnd {
+                    auction_id,
+                    rest_collateral: auction.collateral,
+                    rest_dai: auction.dai,
+                });
+            } else {
+                self.auctions.insert(auction_id, auction);
             }
-            (issuers.len() as u32, total_collateral, total_issue_dai)
+            Ok((slice, owe))
         }
 
-        /// Returns the total cdp amount.
+        /// Anyone can restart an expired auction from the current collateral price
+        #[ink(message)]
+        pub fn redo(&mut self, auction_id: AuctionId) -> Result<()> {
+            if self.status != SystemStatus::Live {
+                return Err(Error::NotLive);
+            }
+            let mut auction = self
+                .auctions
+                .get(&auction_id)
+                .cloned()
+                .ok_or(Error::AuctionNotFound)?;
+            let (_, expired) = self.auction_status(&auction);
+            if !expired {
+                return Err(Error::AuctionNotExpired);
+            }
+            let ilk = self
+                .ilks
+                .get(&auction.ilk_id)
+                .cloned()
+                .ok_or(Error::IlkNotFound)?;
+            if !self.price_is_fresh(&ilk) {
+                return Err(Error::StalePrice);
+            }
+
+            auction.top = ilk.price as u128 * self.auction_buf as u128 / 100;
+            auction.start_block = self.env().block_number();
+            let top = auction.top;
+            self.auctions.insert(auction_id, auction);
+            self.env().emit_event(Redo { auction_id, top });
+            Ok(())
+        }
+
+        /// Mint up to the flash cap of dai to `receiver` and call its `FlashBorrower`
+        /// callback, `amount` plus the fee is pulled from `receiver` through its allowance
+        /// and burned when it returns.
+        /// The whole call reverts if the callback fails or the dai is not returned
+        #[ink(message)]
+        pub fn flash_mint(
+            &mut self,
+            receiver: AccountId,
+            amount: Balance,
+            data: Vec<u8>,
+        ) -> Result<Balance> {
+            if self.status != SystemStatus::Live {
+                return Err(Error::NotLive);
+            }
+            if self.flash_lock || amount == 0 || amount > self.flash_cap {
+                return Err(Error::FlashMintRefused);
+            }
+            // borrower pays the fee rounded up
+            let fee = math::mul_div(
+                amount,
+                self.flash_fee as u128,
+                FEE_DECIMALS as u128,
+                Rounding::Up,
+            )
+            .ok_or(Error::Overflow)?;
+            let repay = amount.checked_add(fee).ok_or(Error::Overflow)?;
+            let surplus = self.surplus.checked_add(fee).ok_or(Error::Overflow)?;
+
+            self.dai_token
+                .mint(receiver, amount)
+                .map_err(|_| Error::DaiMintFailed)?;
+            self.flash_lock = true;
+            let accepted = build_call::<ink_env::DefaultEnvironment>()
+                .callee(receiver)
+                .gas_limit(0)
+                .transferred_value(0)
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ON_FLASH_LOAN))
+                        .push_arg(self.env().caller())
+                        .push_arg(amount)
+                        .push_arg(fee)
+                        .push_arg(data),
+                )
+                .returns::<ReturnType<bool>>()
+                .fire();
+            assert!(matches!(accepted, Ok(true)));
+            // repaid through the allowance the receiver approved to this contract
+            let contract = self.env().account_id();
+            assert!(self
+                .dai_token
+                .transfer_from(receiver, contract, repay)
+                .is_ok());
+            assert!(self.dai_token.burn(contract, repay).is_ok());
+            self.flash_lock = false;
+            self.surplus = surplus;
+            self.env().emit_event(FlashMint {
+                receiver,
+                amount,
+                fee,
+            });
+            Ok(fee)
+        }
+
+        /// Emergency shutdown only admin, freezes prices and fees and stops issuance
+        /// and liquidations
+        #[ink(message)]
+        pub fn shutdown(&mut self) -> Result<()> {
+            self.only_owner()?;
+            if self.status != SystemStatus::Live {
+                return Err(Error::NotLive);
+            }
+            self.drip();
+            let caged_at = self.env().block_timestamp();
+            self.status = SystemStatus::Caged;
+            self.caged_at = caged_at;
+            self.env().emit_event(Shutdown { caged_at });
+            Ok(())
+        }
+
+        /// Anyone can cancel a running auction after shutdown, its collateral and debt
+        /// return to the cdp so it can be skimmed
+        #[ink(message)]
+        pub fn yank(&mut self, auction_id: AuctionId) -> Result<()> {
+            if self.status != SystemStatus::Caged {
+                return Err(Error::NotCaged);
+            }
+            let auction = self
+                .auctions
+                .get(&auction_id)
+                .cloned()
+                .ok_or(Error::AuctionNotFound)?;
+            let cdp = self.cdps.get(&auction.cdp_id).cloned().unwrap();
+            let collateral = cdp
+                .collateral
+                .checked_add(auction.collateral)
+                .ok_or(Error::Overflow)?;
+            let debt = cdp
+                .issue_dai
+                .checked_add(auction.dai)
+                .ok_or(Error::Overflow)?;
+            self.add_to_ilk(auction.ilk_id, auction.collateral, auction.dai)?;
+            self.auctions.take(&auction_id);
+            self.live_auctions -= 1;
+            let rate = self.debt_rate;
+            let cdp = self.cdps.get_mut(&auction.cdp_id).unwrap();
+            cdp.collateral = collateral;
+            cdp.issue_dai = debt;
+            cdp.rate = rate;
+            cdp.auction = None;
+            self.env().emit_event(Yank {
+                auction_id,
+                cdp_id: auction.cdp_id,
+            });
+            Ok(())
+        }
+
+        /// Anyone can skim a cdp after shutdown, collateral covering its debt at the
+        /// frozen price is set aside for dai holders and the debt is cleared
+        #[ink(message)]
+        pub fn skim(&mut self, cdp_id: CdpId) -> Result<()> {
+            if self.status != SystemStatus::Caged {
+                return Err(Error::NotCaged);
+            }
+            if !self.cdps.contains_key(&cdp_id) {
+                return Err(Error::CdpNotFound);
+            }
+            self.charge_fee(cdp_id)?;
+            let cdp = self.cdps.get(&cdp_id).cloned().unwrap();
+            let ilk = self
+                .ilks
+                .get(&cdp.ilk_id)
+                .cloned()
+                .ok_or(Error::IlkNotFound)?;
+            // overflow means the debt is worth more than any collateral
+            let owe = match self.min_collateral(&ilk, cdp.issue_dai, 100) {
+                Err(Error::Overflow) => Balance::MAX,
+                owe => owe?,
+            };
+            let collateral = owe.min(cdp.collateral);
+            // debt the collateral does not cover at the frozen price, rounded up
+            let shortfall = if owe > cdp.collateral {
+                let covered = self.max_debt(&ilk, cdp.collateral, 100)?;
+                cdp.issue_dai.saturating_sub(covered)
+            } else {
+                0
+            };
+
+            self.record_bad_debt(cdp_id, shortfall)?;
+            let cdp_mut = self.cdps.get_mut(&cdp_id).unwrap();
+            cdp_mut.collateral -= collateral;
+            cdp_mut.issue_dai = 0;
+            self.sub_from_ilk(cdp.ilk_id, collateral, cdp.issue_dai);
+            self.ilks.get_mut(&cdp.ilk_id).unwrap().settle_collateral += collateral;
+            self.env().emit_event(Skim {
+                cdp_id,
+                collateral,
+                dai: cdp.issue_dai,
+            });
+            Ok(())
+        }
+
+        /// Only issuer or operator can reclaim the collateral left in a skimmed cdp to
+        /// the issuer after shutdown
+        #[ink(message)]
+        pub fn reclaim_collateral(&mut self, cdp_id: CdpId) -> Result<Balance> {
+            if self.status == SystemStatus::Live {
+                return Err(Error::NotCaged);
+            }
+            let cdp = self.cdps.get(&cdp_id).cloned().ok_or(Error::CdpNotFound)?;
+            if !self.can_manage(cdp_id, self.env().caller()) {
+                return Err(Error::NotIssuer);
+            }
+            if cdp.issue_dai > 0 {
+                return Err(Error::DebtOutstanding);
+            }
+            let ilk = self
+                .ilks
+                .get(&cdp.ilk_id)
+                .cloned()
+                .ok_or(Error::IlkNotFound)?;
+
+            self.push_collateral(&ilk, cdp.issuer, cdp.collateral)?;
+            self.cdps.get_mut(&cdp_id).unwrap().collateral = 0;
+            self.sub_from_ilk(cdp.ilk_id, cdp.collateral, 0);
+            self.env().emit_event(ReclaimCollateral {
+                cdp_id,
+                collateral: cdp.collateral,
+            });
+            Ok(cdp.collateral)
+        }
+
+        /// Anyone can fix the dai redemption ratios once the shutdown wait has passed
+        /// and all debt has been skimmed
+        #[ink(message)]
+        pub fn settle(&mut self) -> Result<()> {
+            if self.status != SystemStatus::Caged {
+                return Err(Error::NotCaged);
+            }
+            if self.env().block_timestamp() < self.caged_at.saturating_add(self.shutdown_wait)
+                || self.total_debt > 0
+                || self.live_auctions > 0
+            {
+                return Err(Error::SettleTooEarly);
+            }
+            let dai_supply = self.dai_token.total_supply();
+            self.settle_dai = dai_supply;
+            self.status = SystemStatus::Settled;
+            self.env().emit_event(Settle { dai_supply });
+            Ok(())
+        }
+
+        /// Burn dai for a pro-rata share of the collateral set aside of every
+        /// collateral type
+        #[ink(message)]
+        pub fn redeem_dai(&mut self, dai: Balance) -> Result<()> {
+            if self.status != SystemStatus::Settled {
+                return Err(Error::NotSettled);
+            }
+            let redeemed_dai = self.redeemed_dai.checked_add(dai).ok_or(Error::Overflow)?;
+            if dai == 0 || redeemed_dai > self.settle_dai {
+                return Err(Error::InsufficientDai);
+            }
+            let shares = (0..self.ilk_count)
+                .map(|ilk_id| {
+                    let ilk = self.ilks.get(&ilk_id).cloned().ok_or(Error::IlkNotFound)?;
+                    // redeemed collateral rounds down
+                    math::mul_div(ilk.settle_collateral, dai, self.settle_dai, Rounding::Down)
+                        .map(|share| (ilk, share))
+                        .ok_or(Error::Overflow)
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            let caller = self.env().caller();
+            self.dai_token
+                .burn(caller, dai)
+                .map_err(|_| Error::DaiBurnFailed)?;
+            self.redeemed_dai = redeemed_dai;
+            for (ilk, share) in shares.iter() {
+                assert!(self.push_collateral(ilk, caller, *share).is_ok());
+            }
+            self.env().emit_event(RedeemDAI { user: caller, dai });
+            Ok(())
+        }
+
+        /// Returns the system status, shutdown time, dai supply at settlement and
+        /// the dai redeemed so far.
+        #[ink(message)]
+        pub fn shutdown_status(&self) -> (SystemStatus, Timestamp, Balance, Balance) {
+            (
+                self.status,
+                self.caged_at,
+                self.settle_dai,
+                self.redeemed_dai,
+            )
+        }
+
+        /// Query auction by id
+        #[ink(message)]
+        pub fn query_auction(&self, auction_id: AuctionId) -> Option<Auction> {
+            self.auctions.get(&auction_id).cloned()
+        }
+
+        /// Returns the current auction price and whether the auction needs a redo.
+        #[ink(message)]
+        pub fn auction_price(&self, auction_id: AuctionId) -> Option<(u128, bool)> {
+            self.auctions
+                .get(&auction_id)
+                .map(|auction| self.auction_status(auction))
+        }
+
+        /// Returns the auction starting price buffer, decay curve and max duration.
+        #[ink(message)]
+        pub fn auction_params(&self) -> (u32, DecayCurve, BlockNumber) {
+            (self.auction_buf, self.auction_curve, self.auction_tail)
+        }
+
+        /// Returns the total auction amount.
+        #[ink(message)]
+        pub fn auction_count(&self) -> u32 {
+            self.auction_count
+        }
+
+        /// Returns the total issuers、total dot collateral、total issue dai.
+        #[ink(message)]
+        pub fn total_supply(&self) -> (u32, Balance, Balance) {
+            let total_collateral = self.ilks.get(&DOT_ILK).unwrap().total_collateral;
+            (self.issuer_count, total_collateral, self.total_debt)
+        }
+
+        /// Returns the open cdp amount.
         #[ink(message)]
         pub fn cdp_count(&self) -> u32 {
             self.cdp_count
         }
 
-        fn only_owner(&self) {
-            assert_eq!(self.env().caller(), self.owner);
+        fn only_owner(&self) -> Result<()> {
+            if self.env().caller() != self.owner {
+                return Err(Error::Unauthorized);
+            }
+            Ok(())
+        }
+
+        fn index_cdp(&mut self, owner: AccountId, cdp_id: CdpId) {
+            let count = self.cdp_count_of(owner);
+            if count == 0 {
+                self.issuer_count += 1;
+            }
+            self.owner_cdps.insert((owner, count), cdp_id);
+            self.cdp_slots.insert(cdp_id, count);
+            self.owner_cdp_count.insert(owner, count + 1);
+        }
+
+        /// Removes cdp from the index of `owner`, the last cdp of the owner takes its slot.
+        fn unindex_cdp(&mut self, owner: AccountId, cdp_id: CdpId) {
+            let slot = match self.cdp_slots.take(&cdp_id) {
+                Some(slot) => slot,
+                None => return,
+            };
+            let last = self.cdp_count_of(owner) - 1;
+            let moved = self.owner_cdps.take(&(owner, last)).unwrap();
+            if slot != last {
+                self.owner_cdps.insert((owner, slot), moved);
+                self.cdp_slots.insert(moved, slot);
+            }
+            if last == 0 {
+                self.owner_cdp_count.take(&owner);
+                self.issuer_count -= 1;
+            } else {
+                self.owner_cdp_count.insert(owner, last);
+            }
+        }
+
+        /// Scale the cdp debt to the given fee rate accumulator.
+        fn with_rate(mut cdp: CDP, rate: u128) -> CDP {
+            cdp.issue_dai = Self::accrue(cdp.issue_dai, cdp.rate, rate);
+            cdp.rate = rate;
+            cdp
+        }
+
+        /// Whether `who` is the issuer or the approved operator of cdp.
+        fn can_manage(&self, cdp_id: CdpId, who: AccountId) -> bool {
+            self.cdps.get(&cdp_id).map(|cdp| cdp.issuer) == Some(who)
+                || self.cdp_operators.get(&cdp_id) == Some(&who)
+        }
+
+        /// Returns the fee rate accumulator as of the current block.
+        fn current_rate(&self) -> u128 {
+            let elapsed = self
+                .env()
+                .block_timestamp()
+                .saturating_sub(self.rate_updated);
+            if elapsed == 0 || self.stability_fee == 0 || self.status != SystemStatus::Live {
+                return self.debt_rate;
+            }
+            // saturates, the accumulator can not realistically reach u128::MAX
+            math::mul_div_wide(
+                self.debt_rate.into(),
+                U256::from(self.stability_fee) * U256::from(elapsed),
+                U256::from(FEE_DECIMALS) * U256::from(YEAR),
+                Rounding::Down,
+            )
+            .and_then(|accrued| math::to_u128(accrued + U256::from(self.debt_rate)))
+            .unwrap_or(u128::MAX)
+        }
+
+        fn record_bad_debt(&mut self, cdp_id: CdpId, dai: Balance) -> Result<()> {
+            if dai > 0 {
+                self.bad_debt = self.bad_debt.checked_add(dai).ok_or(Error::Overflow)?;
+                self.env().emit_event(BadDebt { cdp_id, dai });
+            }
+            Ok(())
+        }
+
+        /// Bring the fee rate accumulator up to date.
+        fn drip(&mut self) {
+            self.debt_rate = self.current_rate();
+            self.rate_updated = self.env().block_timestamp();
+        }
+
+        /// Add the stability fee accrued since the last update to the cdp debt and
+        /// account it as system surplus, nothing is charged on overflow.
+        fn charge_fee(&mut self, cdp_id: CdpId) -> Result<Balance> {
+            let rate = self.debt_rate;
+            let cdp = self.cdps.get(&cdp_id).ok_or(Error::CdpNotFound)?;
+            let debt = Self::accrue(cdp.issue_dai, cdp.rate, rate);
+            let fee = debt - cdp.issue_dai;
+            let ilk_id = cdp.ilk_id;
+            let surplus = self.surplus.checked_add(fee).ok_or(Error::Overflow)?;
+            self.add_to_ilk(ilk_id, 0, fee)?;
+            let cdp = self.cdps.get_mut(&cdp_id).unwrap();
+            cdp.issue_dai = debt;
+            cdp.rate = rate;
+            self.surplus = surplus;
+            if fee > 0 {
+                self.env().emit_event(CollectFee { cdp_id, fee });
+            }
+            Ok(fee)
+        }
+
+        /// Scale debt from the `from` accumulator to the `to` accumulator, rounding up
+        /// and saturating at Balance::MAX.
+        fn accrue(debt: Balance, from: u128, to: u128) -> Balance {
+            if debt == 0 || from == to {
+                return debt;
+            }
+            math::mul_div(debt, to, from, Rounding::Up).unwrap_or(Balance::MAX)
+        }
+
+        /// Returns the current price of the auction and whether it needs a redo.
+        fn auction_status(&self, auction: &Auction) -> (u128, bool) {
+            let elapsed = self
+                .env()
+                .block_number()
+                .saturating_sub(auction.start_block);
+            let price = match self.auction_curve {
+                DecayCurve::Linear(duration) => {
+                    if elapsed >= duration {
+                        0
+                    } else {
+                        math::mul_div(
+                            auction.top,
+                            (duration - elapsed) as u128,
+                            duration as u128,
+                            Rounding::Down,
+                        )
+                        .unwrap_or(0)
+                    }
+                }
+                DecayCurve::StairstepExponential(step, cut) => {
+                    math::rpow(cut as u128 * RATE_ONE / 100, elapsed / step)
+                        .and_then(|factor| math::rmul(auction.top, factor, Rounding::Down))
+                        .unwrap_or(0)
+                }
+            };
+            (price, price == 0 || elapsed > self.auction_tail)
+        }
+
+        fn dai_decimals(&self) -> Result<u128> {
+            let decimals = self
+                .dai_token
+                .token_decimals()
+                .ok_or(Error::DaiDecimalsUnknown)?;
+            Ok(10u128.saturating_pow(decimals as u32))
+        }
+
+        /// Returns the collateral ratio in percent at the current collateral type price,
+        /// rounded down and saturating at u32::MAX.
+        fn calc_collateral_ratio(
+            &self,
+            ilk: &Ilk,
+            collateral: Balance,
+            debt: Balance,
+        ) -> Result<u32> {
+            if debt == 0 {
+                return Ok(u32::MAX);
+            }
+            Ok(math::mul_div_wide(
+                collateral.into(),
+                U256::from(ilk.price) * U256::from(100) * U256::from(self.dai_decimals()?),
+                U256::from(debt) * U256::from(ilk.unit) * U256::from(DOT_PRICE_DECIMALS),
+                Rounding::Down,
+            )
+            .filter(|cr| *cr <= U256::from(u32::MAX))
+            .map_or(u32::MAX, |cr| cr.as_u32()))
+        }
+
+        /// Returns the max debt `collateral` can back at `ratio` percent, rounded down.
+        fn max_debt(&self, ilk: &Ilk, collateral: Balance, ratio: u32) -> Result<Balance> {
+            math::mul_div_wide(
+                collateral.into(),
+                U256::from(ilk.price) * U256::from(100) * U256::from(self.dai_decimals()?),
+                U256::from(ilk.unit) * U256::from(ratio) * U256::from(DOT_PRICE_DECIMALS),
+                Rounding::Down,
+            )
+            .and_then(math::to_u128)
+            .ok_or(Error::Overflow)
+        }
+
+        /// Returns the min collateral backing `debt` at `ratio` percent, rounded up.
+        fn min_collateral(&self, ilk: &Ilk, debt: Balance, ratio: u32) -> Result<Balance> {
+            if debt == 0 {
+                return Ok(0);
+            }
+            if ilk.price == 0 {
+                return Ok(Balance::MAX);
+            }
+            math::mul_div_wide(
+                U256::from(debt) * U256::from(ratio),
+                U256::from(ilk.unit) * U256::from(DOT_PRICE_DECIMALS),
+                U256::from(ilk.price) * U256::from(100) * U256::from(self.dai_decimals()?),
+                Rounding::Up,
+            )
+            .and_then(math::to_u128)
+            .ok_or(Error::Overflow)
+        }
+
+        /// Returns the collateral worth `dai` at the current collateral type price.
+        fn collateral_for(&self, ilk: &Ilk, dai: Balance, rounding: Rounding) -> Result<Balance> {
+            math::mul_div_wide(
+                dai.into(),
+                U256::from(ilk.unit) * U256::from(DOT_PRICE_DECIMALS),
+                U256::from(ilk.price) * U256::from(self.dai_decimals()?),
+                rounding,
+            )
+            .and_then(math::to_u128)
+            .ok_or(Error::Overflow)
+        }
+
+        /// Returns the cdp with its current debt together with its collateral type.
+        fn cdp_with_ilk(&self, cdp_id: CdpId) -> Option<(CDP, Ilk)> {
+            let cdp = self.cdps.get(&cdp_id).cloned()?;
+            let ilk = self.ilks.get(&cdp.ilk_id).cloned()?;
+            Some((Self::with_rate(cdp, self.current_rate()), ilk))
+        }
+
+        fn price_is_fresh(&self, ilk: &Ilk) -> bool {
+            ilk.price_updated > 0
+                && self
+                    .env()
+                    .block_timestamp()
+                    .saturating_sub(ilk.price_updated)
+                    <= self.price_ttl
+        }
+
+        /// Opens a cdp for `issue_dai`.
+        fn open_cdp(
+            &mut self,
+            ilk_id: IlkId,
+            collateral: Balance,
+            cr: u32,
+        ) -> Result<(CdpId, Balance)> {
+            if self.status != SystemStatus::Live {
+                return Err(Error::NotLive);
+            }
+            let ilk = self.ilks.get(&ilk_id).cloned().ok_or(Error::IlkNotFound)?;
+            if !self.price_is_fresh(&ilk) {
+                return Err(Error::StalePrice);
+            }
+            if cr < ilk.min_collateral_ratio {
+                return Err(Error::BelowMinCollateralRatio);
+            }
+            let caller = self.env().caller();
+            let dai = self.max_debt(&ilk, collateral, cr)?;
+            if dai > ilk.debt_ceiling.saturating_sub(ilk.total_debt)
+                || dai > self.debt_ceiling.saturating_sub(self.total_debt)
+            {
+                return Err(Error::DebtCeilingExceeded);
+            }
+            if dai == 0 || dai < self.dust {
+                return Err(Error::DustDebt);
+            }
+            if ilk.total_collateral.checked_add(collateral).is_none() {
+                return Err(Error::Overflow);
+            }
+
+            self.pull_collateral(&ilk, caller, collateral)?;
+            assert!(self.dai_token.mint(caller, dai).is_ok());
+
+            self.drip();
+            let cdp = CDP {
+                issuer: caller,
+                ilk_id,
+                collateral,
+                issue_dai: dai,
+                create_date: self.env().block_timestamp(),
+                rate: self.debt_rate,
+                auction: None,
+            };
+            self.cdp_nonce += 1;
+            self.cdp_count += 1;
+            let cdp_id = self.cdp_nonce;
+            self.cdps.insert(cdp_id, cdp);
+            self.index_cdp(caller, cdp_id);
+            assert!(self.add_to_ilk(ilk_id, collateral, dai).is_ok());
+            self.env().emit_event(IssueDAI {
+                cdp_id,
+                ilk_id,
+                collateral,
+                dai,
+            });
+            Ok((cdp_id, dai))
+        }
+
+        /// Adds collateral to a cdp for `add_collateral`.
+        fn lock_collateral(&mut self, cdp_id: CdpId, collateral: Balance) -> Result<()> {
+            if self.status != SystemStatus::Live {
+                return Err(Error::NotLive);
+            }
+            let caller = self.env().caller();
+            let cdp = self.cdps.get(&cdp_id).cloned().ok_or(Error::CdpNotFound)?;
+            if !self.can_manage(cdp_id, caller) {
+                return Err(Error::NotIssuer);
+            }
+            let ilk = self
+                .ilks
+                .get(&cdp.ilk_id)
+                .cloned()
+                .ok_or(Error::IlkNotFound)?;
+            let total = cdp
+                .collateral
+                .checked_add(collateral)
+                .ok_or(Error::Overflow)?;
+            if ilk.total_collateral.checked_add(collateral).is_none() {
+                return Err(Error::Overflow);
+            }
+            let debt = Self::accrue(cdp.issue_dai, cdp.rate, self.current_rate());
+            let cr = self.calc_collateral_ratio(&ilk, total, debt)?;
+
+            self.pull_collateral(&ilk, caller, collateral)?;
+            self.cdps.get_mut(&cdp_id).unwrap().collateral = total;
+            assert!(self.add_to_ilk(cdp.ilk_id, collateral, 0).is_ok());
+            self.env().emit_event(AddCollateral {
+                cdp_id,
+                add_collateral: collateral,
+                collateral_ratio: cr,
+            });
+            Ok(())
+        }
+
+        /// Returning an error does not revert the call, so a payable message traps instead
+        /// when value was transferred to it, refunding the value.
+        fn revert_if_paid<T>(&self, result: Result<T>) -> Result<T> {
+            assert!(result.is_ok() || self.env().transferred_balance() == 0);
+            result
+        }
+
+        /// Receive collateral from `from`, native dot must be attached to the call and
+        /// erc20 collateral is pulled with `transfer_from`.
+        fn pull_collateral(&self, ilk: &Ilk, from: AccountId, amount: Balance) -> Result<()> {
+            let transferred = self.env().transferred_balance();
+            match ilk.token {
+                None if transferred == amount => Ok(()),
+                Some(token) if transferred == 0 => {
+                    let mut token: Erc20 = FromAccountId::from_account_id(token);
+                    token
+                        .transfer_from(from, self.env().account_id(), amount)
+                        .map_err(|_| Error::TransferFailed)
+                }
+                _ => Err(Error::InvalidCollateralAmount),
+            }
+        }
+
+        /// Send collateral to `to`.
+        fn push_collateral(&self, ilk: &Ilk, to: AccountId, amount: Balance) -> Result<()> {
+            if amount == 0 {
+                return Ok(());
+            }
+            match ilk.token {
+                None => self
+                    .env()
+                    .transfer(to, amount)
+                    .map_err(|_| Error::TransferFailed),
+                Some(token) => {
+                    let mut token: Erc20 = FromAccountId::from_account_id(token);
+                    token
+                        .transfer(to, amount)
+                        .map_err(|_| Error::TransferFailed)
+                }
+            }
+        }
+
+        /// Add collateral and debt to the collateral type and total ledgers, nothing is
+        /// added on overflow.
+        fn add_to_ilk(&mut self, ilk_id: IlkId, collateral: Balance, debt: Balance) -> Result<()> {
+            let ilk = self.ilks.get(&ilk_id).ok_or(Error::IlkNotFound)?;
+            let ilk_collateral = ilk
+                .total_collateral
+                .checked_add(collateral)
+                .ok_or(Error::Overflow)?;
+            let ilk_debt = ilk.total_debt.checked_add(debt).ok_or(Error::Overflow)?;
+            let total_debt = self.total_debt.checked_add(debt).ok_or(Error::Overflow)?;
+            let ilk = self.ilks.get_mut(&ilk_id).unwrap();
+            ilk.total_collateral = ilk_collateral;
+            ilk.total_debt = ilk_debt;
+            self.total_debt = total_debt;
+            Ok(())
+        }
+
+        fn sub_from_ilk(&mut self, ilk_id: IlkId, collateral: Balance, debt: Balance) {
+            let ilk = self.ilks.get_mut(&ilk_id).unwrap();
+            ilk.total_collateral = ilk.total_collateral.saturating_sub(collateral);
+            ilk.total_debt = ilk.total_debt.saturating_sub(debt);
+            self.total_debt = self.total_debt.saturating_sub(debt);
+        }
+
+        /// Check the param change against the current params.
+        fn check_change(&self, change: &ParamChange) -> Result<()> {
+            match *change {
+                ParamChange::Mcr(ilk_id, mcr) => {
+                    let ilk = self.ilks.get(&ilk_id).ok_or(Error::IlkNotFound)?;
+                    if mcr <= ilk.min_liquidation_ratio {
+                        return Err(Error::InvalidRatio);
+                    }
+                }
+                ParamChange::Mlr(ilk_id, mlr) => {
+                    let ilk = self.ilks.get(&ilk_id).ok_or(Error::IlkNotFound)?;
+                    if mlr < 100 || mlr >= ilk.min_collateral_ratio {
+                        return Err(Error::InvalidRatio);
+                    }
+                }
+                ParamChange::IlkCeiling(ilk_id, _) => {
+                    if !self.ilks.contains_key(&ilk_id) {
+                        return Err(Error::IlkNotFound);
+                    }
+                }
+                ParamChange::DebtLimits(..) => {}
+                ParamChange::Penalty(penalty, _, chip) => {
+                    if penalty > MAX_LIQUIDATION_PENALTY || chip > penalty {
+                        return Err(Error::InvalidParam);
+                    }
+                }
+                ParamChange::StabilityFee(sf) => {
+                    if sf > MAX_STABILITY_FEE {
+                        return Err(Error::InvalidParam);
+                    }
+                }
+                ParamChange::Auction(buf, curve, tail) => {
+                    let valid_curve = match curve {
+                        DecayCurve::Linear(duration) => duration > 0,
+                        DecayCurve::StairstepExponential(step, cut) => {
+                            step > 0 && cut > 0 && cut < 100
+                        }
+                    };
+                    if buf < 100 || tail == 0 || !valid_curve {
+                        return Err(Error::InvalidAuctionParams);
+                    }
+                }
+                ParamChange::Oracle(quorum, price_ttl) => {
+                    if quorum == 0 || quorum as usize > self.reporters.len() || price_ttl == 0 {
+                        return Err(Error::InvalidOracleParams);
+                    }
+                }
+                ParamChange::ShutdownWait(_) => {}
+                ParamChange::GovDelay(delay) => {
+                    if delay < MIN_GOV_DELAY || delay > MAX_GOV_DELAY {
+                        return Err(Error::InvalidParam);
+                    }
+                }
+                ParamChange::FlashMint(_, fee) => {
+                    if fee > FEE_DECIMALS {
+                        return Err(Error::InvalidParam);
+                    }
+                }
+                ParamChange::Savings(_) => {}
+                ParamChange::AddReporter(reporter) => {
+                    if self.reporters.contains(&reporter) {
+                        return Err(Error::ReporterExists);
+                    }
+                }
+                ParamChange::RemoveReporter(reporter) => {
+                    if !self.reporters.contains(&reporter) {
+                        return Err(Error::ReporterNotFound);
+                    }
+                    // the remaining reporters must still reach the quorum
+                    if self.reporters.len() <= self.quorum as usize {
+                        return Err(Error::InvalidOracleParams);
+                    }
+                }
+                ParamChange::AddIlk(token, mcr, mlr, _) => {
+                    if mlr < 100 || mlr >= mcr {
+                        return Err(Error::InvalidRatio);
+                    }
+                    if (0..self.ilk_count)
+                        .any(|id| self.ilks.get(&id).unwrap().token == Some(token))
+                    {
+                        return Err(Error::IlkExists);
+                    }
+                    self.token_unit(token)?;
+                }
+            }
+            Ok(())
+        }
+
+        fn apply_change(&mut self, change: ParamChange) {
+            match change {
+                ParamChange::Mcr(ilk_id, mcr) => {
+                    self.ilks.get_mut(&ilk_id).unwrap().min_collateral_ratio = mcr;
+                    self.emit_adjust_ilk(ilk_id);
+                }
+                ParamChange::Mlr(ilk_id, mlr) => {
+                    self.ilks.get_mut(&ilk_id).unwrap().min_liquidation_ratio = mlr;
+                    self.emit_adjust_ilk(ilk_id);
+                }
+                ParamChange::IlkCeiling(ilk_id, debt_ceiling) => {
+                    self.ilks.get_mut(&ilk_id).unwrap().debt_ceiling = debt_ceiling;
+                    self.emit_adjust_ilk(ilk_id);
+                }
+                ParamChange::DebtLimits(debt_ceiling, dust) => {
+                    self.debt_ceiling = debt_ceiling;
+                    self.dust = dust;
+                    self.env()
+                        .emit_event(AdjustDebtLimits { debt_ceiling, dust });
+                }
+                ParamChange::Penalty(penalty, tip, chip) => {
+                    self.liquidation_penalty = penalty;
+                    self.keeper_tip = tip;
+                    self.keeper_chip = chip;
+                    self.env().emit_event(AdjustPenalty { penalty, tip, chip });
+                }
+                ParamChange::StabilityFee(sf) => {
+                    self.drip();
+                    self.stability_fee = sf;
+                    self.env().emit_event(AdjustFee { stability_fee: sf });
+                }
+                ParamChange::Auction(buf, curve, tail) => {
+                    self.auction_buf = buf;
+                    self.auction_curve = curve;
+                    self.auction_tail = tail;
+                    self.env().emit_event(AdjustAuction { buf, curve, tail });
+                }
+                ParamChange::Oracle(quorum, price_ttl) => {
+                    self.quorum = quorum;
+                    self.price_ttl = price_ttl;
+                    self.env().emit_event(AdjustOracle { quorum, price_ttl });
+                }
+                ParamChange::ShutdownWait(wait) => self.shutdown_wait = wait,
+                ParamChange::GovDelay(delay) => self.gov_delay = delay,
+                ParamChange::FlashMint(cap, fee) => {
+                    self.flash_cap = cap;
+                    self.flash_fee = fee;
+                    self.env().emit_event(AdjustFlashMint { cap, fee });
+                }
+                ParamChange::Savings(savings) => self.savings = savings,
+                ParamChange::AddReporter(reporter) => {
+                    self.reporters.push(reporter);
+                    self.env().emit_event(AddReporter { reporter });
+                }
+                ParamChange::RemoveReporter(reporter) => {
+                    self.reporters.retain(|r| *r != reporter);
+                    for ilk_id in 0..self.ilk_count {
+                        self.quotes.take(&(ilk_id, reporter));
+                    }
+                    self.env().emit_event(RemoveReporter { reporter });
+                }
+                ParamChange::AddIlk(token, mcr, mlr, debt_ceiling) => {
+                    let ilk_id = self.ilk_count;
+                    self.ilks.insert(
+                        ilk_id,
+                        Ilk {
+                            token: Some(token),
+                            // decimals were checked by check_change
+                            unit: self.token_unit(token).unwrap(),
+                            price: 0,
+                            price_updated: 0,
+                            min_collateral_ratio: mcr,
+                            min_liquidation_ratio: mlr,
+                            debt_ceiling,
+                            total_collateral: 0,
+                            total_debt: 0,
+                            settle_collateral: 0,
+                        },
+                    );
+                    self.ilk_count += 1;
+                    self.env().emit_event(AddIlk {
+                        ilk_id,
+                        token: Some(token),
+                    });
+                    self.emit_adjust_ilk(ilk_id);
+                }
+            }
+        }
+
+        /// Returns one whole collateral token in its smallest unit.
+        fn token_unit(&self, token: AccountId) -> Result<Balance> {
+            let token_contract: Erc20 = FromAccountId::from_account_id(token);
+            let decimals = token_contract
+                .token_decimals()
+                .ok_or(Error::TokenDecimalsUnknown)?;
+            Ok(10u128.saturating_pow(decimals as u32))
+        }
+
+        fn emit_adjust_ilk(&self, ilk_id: IlkId) {
+            let ilk = self.ilks.get(&ilk_id).unwrap();
+            self.env().emit_event(AdjustIlk {
+                ilk_id,
+                min_collateral_ratio: ilk.min_collateral_ratio,
+                min_liquidation_ratio: ilk.min_liquidation_ratio,
+                debt_ceiling: ilk.debt_ceiling,
+            });
         }
     }
 }
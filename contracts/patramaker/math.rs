@@ -0,0 +1,65 @@
+//! Overflow checked fixed-point arithmetic used by the PatraMaker valuations.
+//!
+//! Products are computed in 256 bits and every division takes an explicit
+//! rounding direction, so callers can round in the protocol's favour: amounts
+//! owed to the system round up, amounts paid out of it round down. Helpers
+//! return `None` on overflow or division by zero instead of trapping.
+
+use primitive_types::U256;
+
+/// 27 decimals fixed-point unit.
+pub const RAY: u128 = 1_000_000_000_000_000_000_000_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    Down,
+    Up,
+}
+
+/// Returns `x * y / z` rounded in the given direction.
+pub fn mul_div(x: u128, y: u128, z: u128, rounding: Rounding) -> Option<u128> {
+    to_u128(mul_div_wide(x.into(), y.into(), z.into(), rounding)?)
+}
+
+/// Returns `x * y / z` rounded in the given direction without narrowing the result.
+pub fn mul_div_wide(x: U256, y: U256, z: U256, rounding: Rounding) -> Option<U256> {
+    if z.is_zero() {
+        return None;
+    }
+    let (quotient, remainder) = x.checked_mul(y)?.div_mod(z);
+    match rounding {
+        Rounding::Up if !remainder.is_zero() => quotient.checked_add(U256::one()),
+        _ => Some(quotient),
+    }
+}
+
+/// Narrow `x` to `u128`, `None` if it does not fit.
+pub fn to_u128(x: U256) -> Option<u128> {
+    if x > U256::from(u128::MAX) {
+        None
+    } else {
+        Some(x.as_u128())
+    }
+}
+
+/// Multiply two RAY numbers.
+pub fn rmul(x: u128, y: u128, rounding: Rounding) -> Option<u128> {
+    mul_div(x, y, RAY, rounding)
+}
+
+/// Raise the RAY number `x` to the power of `n`, every step rounds down.
+pub fn rpow(x: u128, n: u32) -> Option<u128> {
+    let mut base = x;
+    let mut z = RAY;
+    let mut n = n;
+    while n > 0 {
+        if n % 2 == 1 {
+            z = rmul(z, base, Rounding::Down)?;
+        }
+        n /= 2;
+        if n > 0 {
+            base = rmul(base, base, Rounding::Down)?;
+        }
+    }
+    Some(z)
+}
@@ -0,0 +1,257 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use ink_lang as ink;
+
+#[ink::contract]
+mod savings {
+    use dai::Erc20;
+    use ink_env::call::FromAccountId;
+    use ink_storage::{collections::HashMap as StorageMap, Lazy};
+    use ownership::Ownable;
+    use patramaker::{
+        math::{mul_div, Rounding, RAY},
+        PatraMaker,
+    };
+
+    /// Savings rate is expressed in basis points per year.
+    pub const RATE_DECIMALS: u32 = 10_000;
+    /// Blocks per year at six seconds per block.
+    pub const BLOCKS_PER_YEAR: BlockNumber = 365 * 14_400;
+    /// Max annual savings rate in basis points, 100%.
+    pub const MAX_SAVINGS_RATE: u32 = RATE_DECIMALS;
+
+    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum Error {
+        Unauthorized,
+        /// Returned if more dai is withdrawn than deposited plus interest.
+        InsufficientDeposit,
+        InvalidRate,
+        TransferFailed,
+        /// Returned if an amount does not fit in 128 bits.
+        Overflow,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    #[ink(event)]
+    pub struct Deposit {
+        #[ink(topic)]
+        user: AccountId,
+        dai: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Withdraw {
+        #[ink(topic)]
+        user: AccountId,
+        dai: Balance,
+    }
+
+    #[ink(event)]
+    pub struct AdjustRate {
+        savings_rate: u32,
+    }
+
+    #[ink(storage)]
+    pub struct Savings {
+        dai_token: Lazy<Erc20>,
+        // pays the interest out of its surplus
+        maker: Lazy<PatraMaker>,
+        // deposits normalized by chi at deposit time
+        shares: StorageMap<AccountId, Balance>,
+        total_shares: Balance,
+        // annual savings rate in basis points
+        savings_rate: u32,
+        // savings rate accumulator
+        chi: u128,
+        chi_updated: BlockNumber,
+        owner: AccountId,
+    }
+
+    impl Ownable for Savings {
+        #[ink(constructor)]
+        fn new() -> Self {
+            unimplemented!()
+        }
+
+        #[ink(message)]
+        fn owner(&self) -> Option<AccountId> {
+            Some(self.owner)
+        }
+
+        /// transfer contract ownership to new owner.
+        #[ink(message)]
+        fn transfer_ownership(&mut self, new_owner: Option<AccountId>) {
+            assert_eq!(self.owner(), Some(self.env().caller()));
+            if let Some(new_one) = new_owner {
+                self.owner = new_one;
+            }
+        }
+    }
+
+    impl Savings {
+        #[ink(constructor)]
+        pub fn new(dai_contract: AccountId, maker_contract: AccountId) -> Self {
+            assert_ne!(dai_contract, Default::default());
+            assert_ne!(maker_contract, Default::default());
+            let dai_token: Erc20 = FromAccountId::from_account_id(dai_contract);
+            let maker: PatraMaker = FromAccountId::from_account_id(maker_contract);
+            Self {
+                dai_token: Lazy::new(dai_token),
+                maker: Lazy::new(maker),
+                shares: StorageMap::new(),
+                total_shares: 0,
+                savings_rate: 0,
+                chi: RAY,
+                chi_updated: Self::env().block_number(),
+                owner: Self::env().caller(),
+            }
+        }
+
+        /// Deposit dai approved to this contract, it earns the savings rate until
+        /// withdrawn
+        #[ink(message)]
+        pub fn deposit(&mut self, dai: Balance) -> Result<()> {
+            let caller = self.env().caller();
+            self.drip();
+            // depositor receives shares rounded down
+            let shares = Self::to_shares(dai, self.chi, Rounding::Down)?;
+            let balance = self.shares.get(&caller).copied().unwrap_or(0);
+            let total_shares = self
+                .total_shares
+                .checked_add(shares)
+                .ok_or(Error::Overflow)?;
+
+            let contract = self.env().account_id();
+            self.dai_token
+                .transfer_from(caller, contract, dai)
+                .map_err(|_| Error::TransferFailed)?;
+            self.shares.insert(caller, balance + shares);
+            self.total_shares = total_shares;
+            self.env().emit_event(Deposit { user: caller, dai });
+            Ok(())
+        }
+
+        /// Withdraw dai with interest, the interest is paid into this contract out of
+        /// the maker surplus as it accrues
+        #[ink(message)]
+        pub fn withdraw(&mut self, dai: Balance) -> Result<()> {
+            let caller = self.env().caller();
+            self.drip();
+            // withdrawer burns shares rounded up
+            let shares = Self::to_shares(dai, self.chi, Rounding::Up)?;
+            let balance = self.shares.get(&caller).copied().unwrap_or(0);
+            if shares > balance {
+                return Err(Error::InsufficientDeposit);
+            }
+
+            self.dai_token
+                .transfer(caller, dai)
+                .map_err(|_| Error::TransferFailed)?;
+            if balance == shares {
+                self.shares.take(&caller);
+            } else {
+                self.shares.insert(caller, balance - shares);
+            }
+            self.total_shares -= shares;
+            self.env().emit_event(Withdraw { user: caller, dai });
+            Ok(())
+        }
+
+        /// Adjust annual savings rate in basis points only admin
+        #[ink(message)]
+        pub fn adjust_rate(&mut self, savings_rate: u32) -> Result<()> {
+            if self.env().caller() != self.owner {
+                return Err(Error::Unauthorized);
+            }
+            if savings_rate > MAX_SAVINGS_RATE {
+                return Err(Error::InvalidRate);
+            }
+            self.drip();
+            self.savings_rate = savings_rate;
+            self.env().emit_event(AdjustRate { savings_rate });
+            Ok(())
+        }
+
+        /// Anyone can bring the savings rate accumulator up to date, the interest is
+        /// drawn from the maker surplus and forfeited if the maker refuses to pay it
+        #[ink(message)]
+        pub fn drip(&mut self) {
+            let (chi, interest) = self.accrue();
+            if interest == 0 || self.maker.pay_interest(interest).is_ok() {
+                self.chi = chi;
+            }
+            self.chi_updated = self.env().block_number();
+        }
+
+        /// Returns the dai deposited by `owner` including interest.
+        #[ink(message)]
+        pub fn balance_of(&self, owner: AccountId) -> Balance {
+            let shares = self.shares.get(&owner).copied().unwrap_or(0);
+            Self::to_dai(shares, self.accrue().0)
+        }
+
+        /// Returns the dai deposited by all users including interest.
+        #[ink(message)]
+        pub fn total_deposits(&self) -> Balance {
+            Self::to_dai(self.total_shares, self.accrue().0)
+        }
+
+        /// Returns the annual savings rate in basis points and the rate accumulator.
+        #[ink(message)]
+        pub fn savings_params(&self) -> (u32, u128) {
+            (self.savings_rate, self.accrue().0)
+        }
+
+        /// Returns the savings rate accumulator as of the current block capped by the
+        /// maker surplus available, and the interest owed on all deposits.
+        fn accrue(&self) -> (u128, Balance) {
+            let chi = self.current_chi();
+            if chi == self.chi || self.total_shares == 0 {
+                return (chi, 0);
+            }
+            let interest =
+                Self::to_dai(self.total_shares, chi) - Self::to_dai(self.total_shares, self.chi);
+            let available = self.maker.available_surplus();
+            if interest <= available {
+                return (chi, interest);
+            }
+            // rounds down so the interest never exceeds the surplus
+            let chi = mul_div(available, RAY, self.total_shares, Rounding::Down)
+                .and_then(|accrued| self.chi.checked_add(accrued))
+                .unwrap_or(u128::MAX);
+            let interest =
+                Self::to_dai(self.total_shares, chi) - Self::to_dai(self.total_shares, self.chi);
+            (chi, interest)
+        }
+
+        /// Returns the savings rate accumulator as of the current block.
+        fn current_chi(&self) -> u128 {
+            let elapsed = self.env().block_number().saturating_sub(self.chi_updated);
+            if elapsed == 0 || self.savings_rate == 0 {
+                return self.chi;
+            }
+            let accrued = mul_div(
+                self.chi,
+                self.savings_rate as u128 * elapsed as u128,
+                RATE_DECIMALS as u128 * BLOCKS_PER_YEAR as u128,
+                Rounding::Down,
+            );
+            // saturates, the accumulator can not realistically reach u128::MAX
+            accrued
+                .and_then(|accrued| self.chi.checked_add(accrued))
+                .unwrap_or(u128::MAX)
+        }
+
+        /// Scale dai to shares in the given rounding direction.
+        fn to_shares(dai: Balance, chi: u128, rounding: Rounding) -> Result<Balance> {
+            mul_div(dai, RAY, chi, rounding).ok_or(Error::Overflow)
+        }
+
+        /// Scale shares to dai, rounding down and saturating at Balance::MAX.
+        fn to_dai(shares: Balance, chi: u128) -> Balance {
+            mul_div(shares, chi, RAY, Rounding::Down).unwrap_or(Balance::MAX)
+        }
+    }
+}
@@ -5,32 +5,196 @@ use ink_lang as ink;
 #[ink::contract]
 mod patrapixel {
     use ink_prelude::{string::String, vec, vec::Vec};
-    use ink_storage::collections::HashMap as StorageHashMap;
+    use ink_storage::{
+        collections::HashMap as StorageHashMap,
+        traits::{PackedLayout, SpreadLayout},
+    };
+    use ownership::Ownable;
 
+    /// Price of painting a blank pixel.
     pub const DOTS: Balance = 10_000_000_000;
+    /// Repainting a pixel costs this percentage of its last price.
+    pub const REPAINT_PRICE_RATIO: Balance = 150;
+    /// Percentage of a repaint payment that goes to the previous owner, the rest
+    /// goes to the pool.
+    pub const OWNER_SHARE: Balance = 90;
+    /// Colour of a pixel never painted, it is not part of any palette.
+    pub const BLANK: u8 = 0;
+    /// Max number of pixels returned by a region query.
+    pub const MAX_REGION_AREA: u32 = 64 * 64;
+    /// Max number of entries returned by paginated queries.
+    pub const MAX_PAGE_SIZE: u32 = 100;
+    /// Revenue split shares are expressed in basis points of the pool.
+    pub const SHARE_DECIMALS: u32 = 10_000;
+    /// Max number of revenue split recipients.
+    pub const MAX_RECIPIENTS: u32 = 8;
+    /// Max number of edits kept per pixel, older edits are pruned except the one in
+    /// effect at the latest snapshot.
+    pub const MAX_HISTORY: u32 = 16;
+
+    pub type SnapshotId = u32;
+
+    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum Error {
+        /// Returned if the caller is not the owner.
+        Unauthorized,
+        EmptyPoints,
+        /// Returned if a point is outside the canvas.
+        OutOfBounds,
+        /// Returned if a colour is not in the palette.
+        InvalidColor,
+        /// Returned if a point is painted more than once in a batch.
+        DuplicatePoint,
+        /// Returned if the transferred balance does not cover the price.
+        InsufficientPayment,
+        SnapshotNotFound,
+        /// Returned if the edits of a pixel at a snapshot were pruned from its history.
+        HistoryPruned,
+        /// Returned if a palette is empty, repeats a colour or contains `BLANK`.
+        InvalidPalette,
+        /// Returned if a region is empty or larger than `MAX_REGION_AREA`.
+        InvalidRegion,
+        /// Returned if the recipients are empty, repeated or their shares do not add
+        /// up to `SHARE_DECIMALS`.
+        InvalidRecipients,
+        /// Returned if the pool is too small to pay any recipient.
+        EmptyPool,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    #[derive(
+        Debug, PartialEq, Eq, Clone, Copy, scale::Encode, scale::Decode, SpreadLayout, PackedLayout,
+    )]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub struct Pixel {
+        pub color: u8,
+        pub owner: AccountId,
+        // price paid by the owner
+        pub price: Balance,
+    }
+
+    #[derive(
+        Debug, PartialEq, Eq, Clone, Copy, scale::Encode, scale::Decode, SpreadLayout, PackedLayout,
+    )]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub struct Edit {
+        pub painter: AccountId,
+        pub color: u8,
+        pub block: BlockNumber,
+    }
 
     #[ink(event)]
     pub struct PixelUpdate {
         #[ink(topic)]
         creator: AccountId,
+        // painted indexes and colours
+        points: Vec<(u32, u8)>,
+        cost: Balance,
+        block: BlockNumber,
+    }
+
+    #[ink(event)]
+    pub struct Snapshot {
+        #[ink(topic)]
+        snapshot_id: SnapshotId,
+        block: BlockNumber,
+    }
+
+    #[ink(event)]
+    pub struct PoolDeposit {
+        #[ink(topic)]
+        from: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct PoolWithdraw {
+        #[ink(topic)]
+        recipient: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct SetRecipients {
+        recipients: Vec<(AccountId, u32)>,
     }
 
     #[ink(storage)]
     pub struct Patrapixel {
         name: String,
-        metadata: StorageHashMap<u32, u8>,
+        pixels: StorageHashMap<u32, Pixel>,
+        // pixels of each owner by slot, one storage cell per pixel
+        owned: StorageHashMap<(AccountId, u32), u32>,
+        owned_count: StorageHashMap<AccountId, u32>,
+        // slot of each pixel in the index of its owner
+        pixel_slots: StorageHashMap<u32, u32>,
+        // up to `MAX_HISTORY` edits of each pixel, oldest first
+        history: StorageHashMap<u32, Vec<Edit>>,
+        // block from which the oldest kept edit of a pixel is no longer known to be in
+        // effect, set once edits right after it were pruned
+        history_gaps: StorageHashMap<u32, BlockNumber>,
+        // block frozen by each snapshot
+        snapshots: StorageHashMap<SnapshotId, BlockNumber>,
+        snapshot_count: SnapshotId,
         pool: Balance,
         size: (u32, u32),
+        // colour indexes allowed to paint
+        palette: Vec<u8>,
+        // revenue split of the pool in basis points
+        recipients: Vec<(AccountId, u32)>,
+        owner: AccountId,
+    }
+
+    impl Ownable for Patrapixel {
+        #[ink(constructor)]
+        fn new() -> Self {
+            unimplemented!()
+        }
+
+        #[ink(message)]
+        fn owner(&self) -> Option<AccountId> {
+            Some(self.owner)
+        }
+
+        /// transfer contract ownership to new owner.
+        #[ink(message)]
+        fn transfer_ownership(&mut self, new_owner: Option<AccountId>) {
+            assert_eq!(self.owner(), Some(self.env().caller()));
+            if let Some(new_one) = new_owner {
+                self.owner = new_one;
+            }
+        }
     }
 
     impl Patrapixel {
         #[ink(constructor)]
         pub fn new(x: u32, y: u32) -> Self {
+            assert!(x > 0 && y > 0);
+            assert!(x.checked_mul(y).is_some());
+            let caller = Self::env().caller();
             Self {
                 name: "PatraPixel".parse().unwrap(),
-                metadata: Default::default(),
+                pixels: Default::default(),
+                owned: Default::default(),
+                owned_count: Default::default(),
+                pixel_slots: Default::default(),
+                history: Default::default(),
+                history_gaps: Default::default(),
+                snapshots: Default::default(),
+                snapshot_count: 0,
                 pool: 0,
                 size: (x, y),
+                palette: (1..=16).collect(),
+                recipients: vec![(caller, SHARE_DECIMALS)],
+                owner: caller,
             }
         }
 
@@ -39,16 +203,115 @@ mod patrapixel {
             Self::new(320, 180)
         }
 
-        /// Get and returns pixel metadata
+        /// Get and returns pixel metadata, it grows with every painted pixel so
+        /// `metadata_page` or `region` should be preferred
         #[ink(message)]
         pub fn metadata(&self) -> Vec<(u32, u8)> {
             let mut vec = vec![];
-            for (k, v) in self.metadata.iter() {
-                vec.push((*k, *v));
+            for (k, v) in self.pixels.iter() {
+                vec.push((*k, v.color));
             }
             vec
         }
 
+        /// Returns the painted pixels with their colour among the `limit` pixel indexes
+        /// from `start`.
+        #[ink(message)]
+        pub fn metadata_page(&self, start: u32, limit: u32) -> Vec<(u32, u8)> {
+            let end = start
+                .saturating_add(limit.min(MAX_PAGE_SIZE))
+                .min(self.size.0 * self.size.1);
+            (start..end)
+                .filter_map(|index| self.pixels.get(&index).map(|pixel| (index, pixel.color)))
+                .collect()
+        }
+
+        /// Returns the number of painted pixels.
+        #[ink(message)]
+        pub fn painted_count(&self) -> u32 {
+            self.pixels.len()
+        }
+
+        /// Returns the colours of the `width` x `height` rectangle whose top left
+        /// corner is (`x`, `y`) row by row, unpainted pixels are `BLANK`.
+        #[ink(message)]
+        pub fn region(&self, x: u32, y: u32, width: u32, height: u32) -> Result<Vec<u8>> {
+            self.read_region(x, y, width, height, |index| Ok(self.color_at(index)))
+        }
+
+        /// Returns the colours of a rectangle as `region` does, as they were at the
+        /// block frozen by the snapshot.
+        ///
+        /// The latest snapshot can always be read, an older one fails with
+        /// `HistoryPruned` if a pixel was repainted more than `MAX_HISTORY` times since.
+        #[ink(message)]
+        pub fn region_at(
+            &self,
+            snapshot_id: SnapshotId,
+            x: u32,
+            y: u32,
+            width: u32,
+            height: u32,
+        ) -> Result<Vec<u8>> {
+            let block = *self
+                .snapshots
+                .get(&snapshot_id)
+                .ok_or(Error::SnapshotNotFound)?;
+            self.read_region(x, y, width, height, |index| {
+                self.color_at_block(index, block)
+            })
+        }
+
+        /// Freeze the canvas as of the current block only owner, returns the snapshot id
+        #[ink(message)]
+        pub fn snapshot(&mut self) -> Result<SnapshotId> {
+            self.only_owner()?;
+            let snapshot_id = self.snapshot_count + 1;
+            let block = self.env().block_number();
+            self.snapshots.insert(snapshot_id, block);
+            self.snapshot_count = snapshot_id;
+            self.env().emit_event(Snapshot { snapshot_id, block });
+            Ok(snapshot_id)
+        }
+
+        /// Returns the block frozen by the snapshot.
+        #[ink(message)]
+        pub fn snapshot_block(&self, snapshot_id: SnapshotId) -> Option<BlockNumber> {
+            self.snapshots.get(&snapshot_id).copied()
+        }
+
+        #[ink(message)]
+        pub fn snapshot_count(&self) -> SnapshotId {
+            self.snapshot_count
+        }
+
+        /// Returns the kept edits of a pixel, oldest first. These are the last
+        /// `MAX_HISTORY` edits, except that the edit in effect at the latest snapshot is
+        /// kept in their place.
+        #[ink(message)]
+        pub fn history_of(&self, index: u32) -> Vec<Edit> {
+            self.history.get(&index).cloned().unwrap_or_default()
+        }
+
+        /// Returns `rows` rows of the canvas from `start_row` as runs of `(length, colour)`,
+        /// unpainted pixels are `BLANK`. At least one row and otherwise no more rows than
+        /// fit in `MAX_REGION_AREA` pixels are exported.
+        #[ink(message)]
+        pub fn export_rle(&self, start_row: u32, rows: u32) -> Vec<(u32, u8)> {
+            let (width, height) = self.size;
+            let max_rows = (MAX_REGION_AREA / width).max(1);
+            let end_row = start_row.saturating_add(rows.min(max_rows)).min(height);
+            let mut runs: Vec<(u32, u8)> = Vec::new();
+            for index in start_row.min(end_row) * width..end_row * width {
+                let color = self.color_at(index);
+                match runs.last_mut() {
+                    Some(run) if run.1 == color => run.0 += 1,
+                    _ => runs.push((1, color)),
+                }
+            }
+            runs
+        }
+
         #[ink(message)]
         pub fn pool(&self) -> Balance {
             self.pool
@@ -59,23 +322,343 @@ mod patrapixel {
             self.size
         }
 
-        /// update pixel with metadata
+        /// Returns the colour indexes allowed to paint.
+        #[ink(message)]
+        pub fn palette(&self) -> Vec<u8> {
+            self.palette.clone()
+        }
+
+        /// Set the colour indexes allowed to paint only owner, pixels already painted
+        /// keep their colour
+        #[ink(message)]
+        pub fn set_palette(&mut self, palette: Vec<u8>) -> Result<()> {
+            self.only_owner()?;
+            let mut colors = palette.clone();
+            colors.sort_unstable();
+            colors.dedup();
+            if colors.is_empty() || colors.len() != palette.len() || colors.contains(&BLANK) {
+                return Err(Error::InvalidPalette);
+            }
+            self.palette = palette;
+            Ok(())
+        }
+
+        /// Returns the revenue split recipients with their share in basis points.
+        #[ink(message)]
+        pub fn recipients(&self) -> Vec<(AccountId, u32)> {
+            self.recipients.clone()
+        }
+
+        /// Set the revenue split recipients, such as the treasury and the artists'
+        /// fund, only owner
+        #[ink(message)]
+        pub fn set_recipients(&mut self, recipients: Vec<(AccountId, u32)>) -> Result<()> {
+            self.only_owner()?;
+            if recipients.is_empty() || recipients.len() > MAX_RECIPIENTS as usize {
+                return Err(Error::InvalidRecipients);
+            }
+            let mut total: u32 = 0;
+            for (i, &(recipient, share)) in recipients.iter().enumerate() {
+                if share == 0 || recipients[..i].iter().any(|r| r.0 == recipient) {
+                    return Err(Error::InvalidRecipients);
+                }
+                total = total.saturating_add(share);
+            }
+            if total != SHARE_DECIMALS {
+                return Err(Error::InvalidRecipients);
+            }
+            self.recipients = recipients.clone();
+            self.env().emit_event(SetRecipients { recipients });
+            Ok(())
+        }
+
+        /// Anyone can pay the pool out to the recipients pro rata, dust left by
+        /// rounding stays in the pool. Returns the amount paid out, the whole call
+        /// reverts if any transfer fails.
+        #[ink(message)]
+        pub fn withdraw_pool(&mut self) -> Result<Balance> {
+            let pool = self.pool;
+            let payments: Vec<(AccountId, Balance)> = self
+                .recipients
+                .iter()
+                .map(|&(recipient, share)| {
+                    (recipient, pool / SHARE_DECIMALS as u128 * share as u128)
+                })
+                .collect();
+            let paid: Balance = payments.iter().map(|p| p.1).sum();
+            if paid == 0 {
+                return Err(Error::EmptyPool);
+            }
+
+            self.pool -= paid;
+            for (recipient, amount) in payments {
+                assert!(self.env().transfer(recipient, amount).is_ok());
+                self.env().emit_event(PoolWithdraw { recipient, amount });
+            }
+            Ok(paid)
+        }
+
+        /// Returns the colour, owner and last price of a painted pixel.
+        #[ink(message)]
+        pub fn pixel(&self, index: u32) -> Option<Pixel> {
+            self.pixels.get(&index).copied()
+        }
+
+        #[ink(message)]
+        pub fn owner_of(&self, index: u32) -> Option<AccountId> {
+            self.pixels.get(&index).map(|pixel| pixel.owner)
+        }
+
+        /// Returns the number of pixels owned by `owner`.
+        #[ink(message)]
+        pub fn pixel_count_of(&self, owner: AccountId) -> u32 {
+            self.owned_count.get(&owner).copied().unwrap_or(0)
+        }
+
+        /// Returns up to `limit` pixels owned by `owner` from the `start`th, the order
+        /// changes as pixels are repainted by others.
+        #[ink(message)]
+        pub fn pixels_of(&self, owner: AccountId, start: u32, limit: u32) -> Vec<u32> {
+            let end = start
+                .saturating_add(limit.min(MAX_PAGE_SIZE))
+                .min(self.pixel_count_of(owner));
+            (start..end)
+                .filter_map(|slot| self.owned.get(&(owner, slot)).copied())
+                .collect()
+        }
+
+        /// Returns the price of painting a pixel, a blank pixel costs `DOTS` and a
+        /// painted pixel `REPAINT_PRICE_RATIO` percent of its last price.
+        #[ink(message)]
+        pub fn price_of(&self, index: u32) -> Balance {
+            match self.pixels.get(&index) {
+                Some(pixel) => pixel.price.saturating_mul(REPAINT_PRICE_RATIO) / 100,
+                None => DOTS,
+            }
+        }
+
+        /// update pixel with metadata, the caller becomes the owner of the painted
+        /// pixels and `OWNER_SHARE` percent of a repaint price is paid to the previous
+        /// owner. Traps on invalid points or an insufficient payment so the attached
+        /// value is refunded
         #[ink(message, payable)]
-        pub fn update(&mut self, points: Vec<(u32, u8)>) {
-            assert!(points.len() > 0);
+        pub fn update(&mut self, points: Vec<(u32, u8)>) -> Result<()> {
+            let result = self.paint(points);
+            self.revert_if_paid(result)
+        }
+
+        fn paint(&mut self, points: Vec<(u32, u8)>) -> Result<()> {
+            self.check_points(&points)?;
+            let total = points.iter().fold(0, |total: Balance, p| {
+                total.saturating_add(self.price_of(p.0))
+            });
             let cost = self.env().transferred_balance();
-            assert!(cost >= points.len() as u128 * DOTS);
-            points.iter().for_each(|x| {
-                if let Some(v) = self.metadata.get_mut(&x.0) {
-                    *v = x.1;
-                } else {
-                    self.metadata.insert(x.0, x.1);
+            if cost < total {
+                return Err(Error::InsufficientPayment);
+            }
+
+            let caller = self.env().caller();
+            let block = self.env().block_number();
+            // payments owed to previous owners
+            let mut payouts: Vec<(AccountId, Balance)> = Vec::new();
+            for &(index, color) in points.iter() {
+                let price = self.price_of(index);
+                let previous = self.pixels.insert(
+                    index,
+                    Pixel {
+                        color,
+                        owner: caller,
+                        price,
+                    },
+                );
+                if let Some(previous) = previous {
+                    let share = price / 100 * OWNER_SHARE;
+                    match payouts.iter_mut().find(|p| p.0 == previous.owner) {
+                        Some(payout) => payout.1 += share,
+                        None => payouts.push((previous.owner, share)),
+                    }
+                    self.unindex_pixel(previous.owner, index);
                 }
+                self.index_pixel(caller, index);
+                self.record_edit(
+                    index,
+                    Edit {
+                        painter: caller,
+                        color,
+                        block,
+                    },
+                );
+            }
+            let mut paid: Balance = 0;
+            for (owner, share) in payouts {
+                assert!(self.env().transfer(owner, share).is_ok());
+                paid += share;
+            }
+            self.pool += cost - paid;
+            self.env().emit_event(PoolDeposit {
+                from: caller,
+                amount: cost - paid,
             });
-            self.pool += cost;
             self.env().emit_event(PixelUpdate {
-                creator: self.env().caller(),
+                creator: caller,
+                points,
+                cost,
+                block,
             });
+            Ok(())
+        }
+
+        /// Check the points are inside the canvas, painted with palette colours and
+        /// not repeated.
+        fn check_points(&self, points: &[(u32, u8)]) -> Result<()> {
+            if points.is_empty() {
+                return Err(Error::EmptyPoints);
+            }
+            let area = self.size.0 * self.size.1;
+            for &(index, color) in points {
+                if index >= area {
+                    return Err(Error::OutOfBounds);
+                }
+                if !self.palette.contains(&color) {
+                    return Err(Error::InvalidColor);
+                }
+            }
+            let mut indexes: Vec<u32> = points.iter().map(|p| p.0).collect();
+            indexes.sort_unstable();
+            if indexes.windows(2).any(|w| w[0] == w[1]) {
+                return Err(Error::DuplicatePoint);
+            }
+            Ok(())
+        }
+
+        /// Check the rectangle is not empty, inside the canvas and not larger than
+        /// `MAX_REGION_AREA`.
+        fn check_region(&self, x: u32, y: u32, width: u32, height: u32) -> Result<()> {
+            if width == 0 || height == 0 || width.saturating_mul(height) > MAX_REGION_AREA {
+                return Err(Error::InvalidRegion);
+            }
+            let right = x.checked_add(width).ok_or(Error::OutOfBounds)?;
+            let bottom = y.checked_add(height).ok_or(Error::OutOfBounds)?;
+            if right > self.size.0 || bottom > self.size.1 {
+                return Err(Error::OutOfBounds);
+            }
+            Ok(())
+        }
+
+        /// Read the colours of a checked rectangle row by row.
+        fn read_region<F>(
+            &self,
+            x: u32,
+            y: u32,
+            width: u32,
+            height: u32,
+            color: F,
+        ) -> Result<Vec<u8>>
+        where
+            F: Fn(u32) -> Result<u8>,
+        {
+            self.check_region(x, y, width, height)?;
+            let mut colors = Vec::with_capacity((width * height) as usize);
+            for row in y..y + height {
+                for col in x..x + width {
+                    colors.push(color(row * self.size.0 + col)?);
+                }
+            }
+            Ok(colors)
+        }
+
+        /// Returns the colour of a pixel at the end of `block`.
+        fn color_at_block(&self, index: u32, block: BlockNumber) -> Result<u8> {
+            let edits = match self.history.get(&index) {
+                Some(edits) => edits,
+                None => return Ok(BLANK),
+            };
+            let gap = self.history_gaps.get(&index).copied();
+            match edits.iter().rposition(|edit| edit.block <= block) {
+                // edits right after the oldest one were pruned
+                Some(0) if gap.map_or(false, |gap| gap <= block) => Err(Error::HistoryPruned),
+                Some(pos) => Ok(edits[pos].color),
+                // a full history may have pruned the edit in effect at `block`
+                None if edits.len() >= MAX_HISTORY as usize => Err(Error::HistoryPruned),
+                None => Ok(BLANK),
+            }
+        }
+
+        /// Append an edit to the history of a pixel. A full history drops its oldest
+        /// edit, unless that edit is in effect at the latest snapshot, then the edit
+        /// following it is dropped instead.
+        fn record_edit(&mut self, index: u32, edit: Edit) {
+            let snapshot_block = self.snapshots.get(&self.snapshot_count).copied();
+            let edits = match self.history.get_mut(&index) {
+                Some(edits) => edits,
+                None => {
+                    self.history.insert(index, vec![edit]);
+                    return;
+                }
+            };
+            if edits.len() >= MAX_HISTORY as usize {
+                let frozen = snapshot_block.and_then(|snapshot_block| {
+                    edits.iter().rposition(|edit| edit.block <= snapshot_block)
+                });
+                if frozen == Some(0) {
+                    let pruned = edits.remove(1);
+                    if !self.history_gaps.contains_key(&index) {
+                        self.history_gaps.insert(index, pruned.block);
+                    }
+                } else {
+                    edits.remove(0);
+                    self.history_gaps.take(&index);
+                }
+            }
+            edits.push(edit);
+        }
+
+        fn color_at(&self, index: u32) -> u8 {
+            self.pixels
+                .get(&index)
+                .map(|pixel| pixel.color)
+                .unwrap_or(BLANK)
+        }
+
+        fn only_owner(&self) -> Result<()> {
+            if self.env().caller() != self.owner {
+                return Err(Error::Unauthorized);
+            }
+            Ok(())
+        }
+
+        /// Returning an error does not revert the call, so `update` traps instead when
+        /// value was transferred to it, refunding the painter.
+        fn revert_if_paid<T>(&self, result: Result<T>) -> Result<T> {
+            assert!(result.is_ok() || self.env().transferred_balance() == 0);
+            result
+        }
+
+        fn index_pixel(&mut self, owner: AccountId, index: u32) {
+            let count = self.pixel_count_of(owner);
+            self.owned.insert((owner, count), index);
+            self.pixel_slots.insert(index, count);
+            self.owned_count.insert(owner, count + 1);
+        }
+
+        /// Removes pixel from the index of `owner`, the last pixel of the owner takes
+        /// its slot.
+        fn unindex_pixel(&mut self, owner: AccountId, index: u32) {
+            let slot = match self.pixel_slots.take(&index) {
+                Some(slot) => slot,
+                None => return,
+            };
+            let last = self.pixel_count_of(owner) - 1;
+            let moved = self.owned.take(&(owner, last)).unwrap();
+            if slot != last {
+                self.owned.insert((owner, slot), moved);
+                self.pixel_slots.insert(moved, slot);
+            }
+            if last == 0 {
+                self.owned_count.take(&owner);
+            } else {
+                self.owned_count.insert(owner, last);
+            }
         }
     }
 }
@@ -28,6 +28,6 @@ export default {
     }
   },
   mocha: {
-    timeout: 60000
+    timeout: 300000
   }
 } as RedspotUserConfig;
@@ -15,8 +15,8 @@ async function run() {
   const balance = await api.query.system.account(signer.address);
   console.log('Balance: ', balance.toHuman());
 
-  const daiContractFactory = await getContractFactory('erc20_issue', signer);
-  const daiContract = await daiContractFactory.deployed('IErc20,new', '0', 'Maker DAI', 'DAI', '18', {
+  const daiContractFactory = await getContractFactory('dai', signer);
+  const daiContract = await daiContractFactory.deployed('new', '0', 'Maker DAI', 'DAI', '18', {
     gasLimit: '200000000000',
     value: '0',
     salt: 'Maker DAI Token'
@@ -27,7 +27,12 @@ async function run() {
   );
   console.log('');
 
-  const contract = await contractFactory.deployed('new', daiContract.address, {
+  // price reporters are comma separated in REPORTERS, the deployer reports on a dev chain.
+  // Later reporter changes wait for the governance delay like any other param change
+  const reporters = process.env.REPORTERS ? process.env.REPORTERS.split(',') : [signer.address];
+
+  // param changes wait two days before they can be executed
+  const contract = await contractFactory.deployed('new', daiContract.address, 172800000, reporters, {
     gasLimit: '200000000000',
     value: '0',
     salt: 'PatraMaker'
@@ -37,11 +42,16 @@ async function run() {
     contract.address.toString()
   );
 
-  // transfer dai contract ownership to maker
-  await daiContract.tx['ownable,transferOwnership'](contract.address.toString())
+  // authorise maker to mint and burn dai
+  await daiContract.tx.addMinter(contract.address);
+
+  // the deployer reports the first dot price
+  if (!process.env.REPORTERS) {
+    await contract.tx.submitPrice(0, 3500);
+  }
 
   // init dai with 100k DOT
-  await contract.tx.issueDai(200, {
+  await contract.tx.issueDai(0, 1000000000000000, 200, {
     value: 1000000000000000
   });
 
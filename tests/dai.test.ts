@@ -0,0 +1,71 @@
+import BN from 'bn.js';
+import { expect } from 'chai';
+import { patract, network, artifacts } from 'redspot';
+
+const { getContractFactory, getRandomSigner } = patract;
+
+const { api, getSigners } = network;
+
+describe('DAI', () => {
+  after(() => {
+    return api.disconnect();
+  });
+
+  async function setup() {
+    const one = new BN(10).pow(new BN(api.registry.chainDecimals));
+    const signers = await getSigners();
+    const Alice = signers[0];
+    const sender = await getRandomSigner(Alice, one.muln(100));
+    const contractFactory = await getContractFactory('dai', sender);
+    const contract = await contractFactory.deploy('new', '1000000', 'Maker DAI', 'DAI', '18');
+    const abi = artifacts.readArtifact('dai');
+    const receiver = await getRandomSigner();
+
+    return { sender, contractFactory, contract, abi, receiver, Alice, one };
+  }
+
+  it('Assigns initial balance', async () => {
+    const { contract, sender } = await setup();
+    const result = await contract.query.balanceOf(sender.address);
+    expect(result.output).to.equal(1000000);
+  });
+
+  it('Only minter can mint', async () => {
+    const { contract, sender, receiver } = await setup();
+    await expect(contract.tx.mint(receiver.address, 7))
+      .to.not.emit(contract, 'Transfer');
+    await expect(contract.tx.addMinter(sender.address))
+      .to.emit(contract, 'AddMinter')
+      .withArgs(sender.address);
+    await expect(contract.tx.mint(receiver.address, 7))
+      .to.emit(contract, 'Transfer');
+    const result = await contract.query.balanceOf(receiver.address);
+    expect(result.output).to.equal(7);
+  });
+
+  it('Removed minter can not burn', async () => {
+    const { contract, sender } = await setup();
+    await contract.tx.addMinter(sender.address);
+    await expect(contract.tx.removeMinter(sender.address))
+      .to.emit(contract, 'RemoveMinter');
+    await expect(contract.tx.burn(sender.address, 7))
+      .to.not.emit(contract, 'Transfer');
+  });
+
+  it('Permit approves with owner signature', async () => {
+    const { contract, receiver } = await setup();
+    const owner = network.keyring.addFromUri('//Permit', {}, 'ecdsa');
+    const deadline = Date.now() + 600000;
+    const payload = await contract.query.permitPayload(owner.address, receiver.address, 7, 0, deadline);
+    const signature = owner.sign(payload.output?.toU8a(true) as Uint8Array);
+    await expect(contract.tx.permit(owner.address, receiver.address, 7, deadline, signature))
+      .to.emit(contract, 'Approval')
+      .withArgs(owner.address, receiver.address, 7);
+    const allowance = await contract.query.allowance(owner.address, receiver.address);
+    expect(allowance.output).to.equal(7);
+    const nonce = await contract.query.nonceOf(owner.address);
+    expect(nonce.output).to.equal(1);
+    await expect(contract.tx.permit(owner.address, receiver.address, 7, deadline, signature))
+      .to.not.emit(contract, 'Approval');
+  });
+});
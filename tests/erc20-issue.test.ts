@@ -0,0 +1,53 @@
+import BN from 'bn.js';
+import { expect } from 'chai';
+import { patract, network, artifacts } from 'redspot';
+
+const { getContractFactory, getRandomSigner } = patract;
+
+const { api, getSigners } = network;
+
+describe('ERC20 Issue', () => {
+  after(() => {
+    return api.disconnect();
+  });
+
+  async function setup() {
+    const one = new BN(10).pow(new BN(api.registry.chainDecimals));
+    const signers = await getSigners();
+    const Alice = signers[0];
+    const sender = await getRandomSigner(Alice, one.muln(100));
+    const contractFactory = await getContractFactory('erc20_issue', sender);
+    const contract = await contractFactory.deploy('iErc20,new', '1000000', 'Jupiter BTC', 'jBTC', '8');
+    const abi = artifacts.readArtifact('erc20_issue');
+    const receiver = await getRandomSigner();
+
+    return { sender, contractFactory, contract, abi, receiver, Alice, one };
+  }
+
+  it('Permit approves with owner signature', async () => {
+    const { contract, receiver } = await setup();
+    const owner = network.keyring.addFromUri('//Permit', {}, 'ecdsa');
+    const deadline = Date.now() + 600000;
+    const payload = await contract.query.permitPayload(owner.address, receiver.address, 7, 0, deadline);
+    const signature = owner.sign(payload.output?.toU8a(true) as Uint8Array);
+    await expect(contract.tx.permit(owner.address, receiver.address, 7, deadline, signature))
+      .to.emit(contract, 'Approval')
+      .withArgs(owner.address, receiver.address, 7);
+    const allowance = await contract.query['iErc20,allowance'](owner.address, receiver.address);
+    expect(allowance.output).to.equal(7);
+    const nonce = await contract.query.nonceOf(owner.address);
+    expect(nonce.output).to.equal(1);
+    await expect(contract.tx.permit(owner.address, receiver.address, 7, deadline, signature))
+      .to.not.emit(contract, 'Approval');
+  });
+
+  it('Permit rejects expired deadline', async () => {
+    const { contract, receiver } = await setup();
+    const owner = network.keyring.addFromUri('//Permit', {}, 'ecdsa');
+    const deadline = Date.now() - 600000;
+    const payload = await contract.query.permitPayload(owner.address, receiver.address, 7, 0, deadline);
+    const signature = owner.sign(payload.output?.toU8a(true) as Uint8Array);
+    await expect(contract.tx.permit(owner.address, receiver.address, 7, deadline, signature))
+      .to.not.emit(contract, 'Approval');
+  });
+});
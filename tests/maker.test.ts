@@ -6,6 +6,13 @@ const { getContractFactory, getRandomSigner } = patract;
 
 const { api, getSigners } = network;
 
+// min governance delay of the contract, one minute
+const GOV_DELAY = 60000;
+
+function sleep(ms: number) {
+  return new Promise((resolve) => setTimeout(resolve, ms));
+}
+
 describe('ERC20', () => {
   after(() => {
     return api.disconnect();
@@ -17,55 +24,183 @@ describe('ERC20', () => {
     const Alice = signers[0];
     const sender = await getRandomSigner(Alice, one.muln(100));
 
-    const daiContractFactory = await getContractFactory('erc20_issue', sender);
-    const daiContract = await daiContractFactory.deployed('IErc20,new', '0', 'Maker DAI', 'DAI', '18');
+    const daiContractFactory = await getContractFactory('dai', sender);
+    const daiContract = await daiContractFactory.deployed('new', '0', 'Maker DAI', 'DAI', '18');
     const contractFactory = await getContractFactory('patramaker', sender);
-    const contract = await contractFactory.deploy('new', daiContract.address);
-    await daiContract.tx['ownable,transferOwnership'](contract.address.toString())
+    const contract = await contractFactory.deploy('new', daiContract.address, GOV_DELAY, [sender.address]);
+    await daiContract.tx.addMinter(contract.address);
+    await contract.tx.submitPrice(0, 3500);
     const abi = artifacts.readArtifact('patramaker');
     const receiver = await getRandomSigner();
 
     return { sender, contractFactory, contract, abi, receiver, Alice, one, daiContract };
   }
 
+  async function govern(contract, ...changes) {
+    for (const change of changes) {
+      await contract.tx.queueChange(change);
+    }
+    const count = await contract.query.proposalCount();
+    await sleep(GOV_DELAY + 6000);
+    const last = Number(count.output?.toString());
+    for (let id = last - changes.length + 1; id <= last; id++) {
+      await contract.tx.executeChange(id);
+    }
+  }
+
+  it('issue dai with erc20 collateral', async () => {
+    const { contract, sender } = await setup();
+    const tokenFactory = await getContractFactory('erc20_issue', sender);
+    const jbtc = await tokenFactory.deployed('IErc20,new', '100000000000', 'Jupiter BTC', 'jBTC', '8');
+    await govern(contract, { AddIlk: [jbtc.address, 150, 110, '1000000000000000000000000'] });
+    await contract.tx.submitPrice(1, 5000000);
+    await jbtc.tx['iErc20,approve'](contract.address, 100000000);
+    await expect(contract.tx.issueDai(1, 100000000, 200))
+      .to.emit(contract, 'IssueDAI');
+  });
+
   it('issue dai', async () => {
     const { contract } = await setup();
-    await expect(contract.tx.issueDai(200, {
+    await expect(contract.tx.issueDai(0, 1000000000000000, 200, {
       value: 1000000000000000
     })).to.emit(contract, 'IssueDAI');
   });
 
+  it('reject cdp without debt', async () => {
+    const { contract } = await setup();
+    await expect(contract.tx.issueDai(0, 0, 200))
+      .to.not.emit(contract, 'IssueDAI');
+  });
+
+  it('reject dai above debt ceiling', async () => {
+    const { contract } = await setup();
+    await govern(contract, { DebtLimits: ['1000000000000000000000', 0] });
+    const before = await api.query.system.account(contract.address);
+    await expect(contract.tx.issueDai(0, 1000000000000000, 200, {
+      value: 1000000000000000
+    })).to.not.emit(contract, 'IssueDAI');
+    // the attached dot is refunded
+    const after = await api.query.system.account(contract.address);
+    expect(after.data.free.toString()).to.equal(before.data.free.toString());
+  });
+
+  it('list cdps of owner', async () => {
+    const { contract, sender } = await setup();
+    await contract.tx.issueDai(0, 1000000000000000, 200, {
+      value: 1000000000000000
+    });
+    const count = await contract.query.cdpCountOf(sender.address);
+    expect(count.output?.toString()).to.equal('1');
+    const ids = await contract.query.cdpsOf(sender.address, 0, 10);
+    expect(ids.output?.toHuman()).to.deep.equal(['1']);
+    const page = await contract.query.listCdps(1, 10);
+    expect(page.output?.toJSON()).to.have.length(1);
+  });
+
+  it('query cdp health', async () => {
+    const { contract } = await setup();
+    await contract.tx.issueDai(0, 1000000000000000, 200, {
+      value: 1000000000000000
+    });
+    const cr = await contract.query.collateralRatio(1);
+    expect(cr.output?.toHuman()).to.equal('199');
+    await contract.tx.submitPrice(0, 1900);
+    const unsafe = await contract.query.unsafeCdps(1, 10);
+    expect(unsafe.output?.toHuman()).to.deep.equal(['1']);
+  });
+
   it('add collateral', async () => {
     const { contract } = await setup();
-    await contract.tx.issueDai(200, {
+    await contract.tx.issueDai(0, 1000000000000000, 200, {
       value: 1000000000000000
     });
-    await expect(contract.tx.addCollateral(1, {
+    await expect(contract.tx.addCollateral(1, 500000000000, {
       value: 500000000000
     })).to.emit(contract, 'AddCollateral');
   });
 
   it('minus collateral', async () => {
     const { contract } = await setup();
-    await contract.tx.issueDai(200, {
+    await contract.tx.issueDai(0, 1000000000000000, 200, {
       value: 1000000000000000
     });
     await expect(contract.tx.minusCollateral(1, 500000000000))
       .to.emit(contract, 'MinusCollateral');
   });
 
+  it('refuse minus collateral below min collateral ratio', async () => {
+    const { contract } = await setup();
+    await contract.tx.issueDai(0, 1000000000000000, 200, {
+      value: 1000000000000000
+    });
+    await expect(contract.tx.minusCollateral(1, 500000000000000))
+      .to.not.emit(contract, 'MinusCollateral');
+  });
+
+  it('draw dai', async () => {
+    const { contract } = await setup();
+    await contract.tx.issueDai(0, 1000000000000000, 200, {
+      value: 1000000000000000
+    });
+    await expect(contract.tx.drawDai(1, 1000000000000000000))
+      .to.emit(contract, 'DrawDAI');
+  });
+
+  it('transfer cdp and approve operator', async () => {
+    const { contract, receiver } = await setup();
+    await contract.tx.issueDai(0, 1000000000000000, 200, {
+      value: 1000000000000000
+    });
+    await expect(contract.tx.approveCdpOperator(1, receiver.address))
+      .to.emit(contract, 'ApproveCDPOperator');
+    await expect(contract.tx.transferCdp(1, receiver.address))
+      .to.emit(contract, 'TransferCDP');
+  });
+
+  it('operator draws dai to the issuer', async () => {
+    const { contract, sender, Alice, one, daiContract } = await setup();
+    await contract.tx.issueDai(0, 1000000000000000, 200, {
+      value: 1000000000000000
+    });
+    const operator = await getRandomSigner(Alice, one.muln(10));
+    await contract.tx.approveCdpOperator(1, operator.address);
+    const before = await daiContract.query.balanceOf(sender.address);
+    await expect(contract.connect(operator).tx.drawDai(1, '1000000000000000000'))
+      .to.emit(contract, 'DrawDAI');
+    const after = await daiContract.query.balanceOf(sender.address);
+    expect(new BN(after.output?.toString()).sub(new BN(before.output?.toString())).toString())
+      .to.equal('1000000000000000000');
+    const operatorDai = await daiContract.query.balanceOf(operator.address);
+    expect(operatorDai.output?.toString()).to.equal('0');
+  });
+
   it('withdraw dot', async () => {
     const { contract } = await setup();
-    await contract.tx.issueDai(200, {
+    await contract.tx.issueDai(0, 1000000000000000, 200, {
       value: 1000000000000000
     });
     await expect(contract.tx.withdrawDot(1, 2000000000000000000))
       .to.emit(contract, 'Withdraw');
   });
 
+  it('repay dai and close cdp', async () => {
+    const { contract } = await setup();
+    await contract.tx.issueDai(0, 1000000000000000, 200, {
+      value: 1000000000000000
+    });
+    await expect(contract.tx.repayDai(1, '1000000000000000000'))
+      .to.emit(contract, 'RepayDAI');
+    await expect(contract.tx.closeCdp(1))
+      .to.emit(contract, 'CloseCDP');
+    const count = await contract.query.cdpCount();
+    expect(count.output?.toString()).to.equal('0');
+    const cdp = await contract.query.queryCdp(1);
+    expect(cdp.output?.isNone).to.be.true;
+  });
+
   it('liquidate collateral', async () => {
     const { contract } = await setup();
-    await contract.tx.issueDai(200, {
+    await contract.tx.issueDai(0, 1000000000000000, 200, {
       value: 1000000000000000
     });
     await contract.tx.withdrawDot(1, 2000000000000000000);
@@ -73,4 +208,131 @@ describe('ERC20', () => {
       .to.emit(contract, ' Liquidate');
   });
 
+  it('split liquidation penalty', async () => {
+    const { contract } = await setup();
+    await govern(contract, { Penalty: [1000, 0, 500] });
+    await contract.tx.issueDai(0, 1000000000000000, 200, {
+      value: 1000000000000000
+    });
+    await contract.tx.submitPrice(0, 1850);
+    await expect(contract.tx.liquidateCollateral(1, '875000000000000000000000'))
+      .to.emit(contract, 'Liquidate');
+    const solvency = await contract.query.solvency();
+    expect(solvency.output?.toHuman()).to.deep.equal([
+      '43,750,000,000,000,000,000,000',
+      '0',
+      '875,000,000,000,000,000,000,000'
+    ]);
+  });
+
+  it('write off bad debt', async () => {
+    const { contract } = await setup();
+    await contract.tx.issueDai(0, 1000000000000000, 200, {
+      value: 1000000000000000
+    });
+    await contract.tx.submitPrice(0, 100);
+    await expect(contract.tx.liquidateCollateral(1, '875000000000000000000000'))
+      .to.emit(contract, 'BadDebt');
+    const solvency = await contract.query.solvency();
+    expect(solvency.output?.toHuman()).to.deep.equal(['0', '875,000,000,000,000,000,000,000', '0']);
+  });
+
+  it('kick and take auction', async () => {
+    const { contract } = await setup();
+    await contract.tx.issueDai(0, 1000000000000000, 200, {
+      value: 1000000000000000
+    });
+    await contract.tx.submitPrice(0, 1900);
+    await expect(contract.tx.kick(1)).to.emit(contract, 'Kick');
+    await expect(contract.tx.take(1, 500000000000000, 1000000))
+      .to.emit(contract, 'Take');
+  });
+
+  it('refuse flash mint above cap', async () => {
+    const { contract, receiver } = await setup();
+    await govern(contract, { FlashMint: ['1000000000000000000000', 9] });
+    const params = await contract.query.flashParams();
+    expect(params.output?.toHuman()).to.deep.include('9');
+    await expect(contract.tx.flashMint(receiver.address, '2000000000000000000000', '0x'))
+      .to.not.emit(contract, 'FlashMint');
+  });
+
+  it('charge stability fee', async () => {
+    const { contract } = await setup();
+    await govern(contract, { StabilityFee: 500 });
+    await contract.tx.issueDai(0, 1000000000000000, 200, {
+      value: 1000000000000000
+    });
+    await expect(contract.tx.withdrawDot(1, 2000000000000000000))
+      .to.emit(contract, 'CollectFee');
+  });
+
+  it('shutdown and skim', async () => {
+    const { contract } = await setup();
+    await contract.tx.issueDai(0, 1000000000000000, 200, {
+      value: 1000000000000000
+    });
+    await expect(contract.tx.shutdown()).to.emit(contract, 'Shutdown');
+    await expect(contract.tx.skim(1)).to.emit(contract, 'Skim');
+    await expect(contract.tx.reclaimCollateral(1))
+      .to.emit(contract, 'ReclaimCollateral');
+  });
+
+  it('submit price', async () => {
+    const { contract } = await setup();
+    await expect(contract.tx.submitPrice(0, 3600))
+      .to.emit(contract, 'UpdatePrice');
+    const feed = await contract.query.priceFeed(0);
+    expect(feed.output?.toHuman()).to.deep.include('3,600');
+  });
+
+  it('queue and execute param change', async () => {
+    const { contract } = await setup();
+    await expect(contract.tx.queueChange({ Mcr: [0, 160] }))
+      .to.emit(contract, 'QueueChange');
+    await sleep(GOV_DELAY + 6000);
+    await expect(contract.tx.executeChange(1))
+      .to.emit(contract, 'ExecuteChange');
+    const ilk = await contract.query.queryIlk(0);
+    expect(ilk.output?.toHuman()).to.deep.include({ min_collateral_ratio: '160' });
+  });
+
+  it('reject param change before eta', async () => {
+    const { contractFactory, daiContract } = await setup();
+    const contract = await contractFactory.deploy('new', daiContract.address, 86400000, []);
+    await expect(contract.tx.queueChange({ Mlr: [0, 160] }))
+      .to.not.emit(contract, 'QueueChange');
+    await expect(contract.tx.queueChange({ GovDelay: 0 }))
+      .to.not.emit(contract, 'QueueChange');
+    await expect(contract.tx.queueChange({ Mlr: [0, 120] }))
+      .to.emit(contract, 'QueueChange');
+    await expect(contract.tx.executeChange(1))
+      .to.not.emit(contract, 'ExecuteChange');
+    await expect(contract.tx.cancelChange(1))
+      .to.emit(contract, 'CancelChange');
+  });
+
+  it('reject unreachable oracle quorum', async () => {
+    const { contract, sender } = await setup();
+    await expect(contract.tx.queueChange({ Oracle: [2, 3600000] }))
+      .to.not.emit(contract, 'QueueChange');
+    await expect(contract.tx.queueChange({ RemoveReporter: sender.address }))
+      .to.not.emit(contract, 'QueueChange');
+  });
+
+  it('reject stale price', async () => {
+    const { contract, sender, receiver } = await setup();
+    await govern(
+      contract,
+      { AddReporter: receiver.address },
+      { RemoveReporter: sender.address },
+      { Oracle: [1, 1] }
+    );
+    const reporters = await contract.query.reporters();
+    expect(reporters.output?.toHuman()).to.deep.equal([receiver.address]);
+    await expect(contract.tx.issueDai(0, 1000000000000000, 200, {
+      value: 1000000000000000
+    })).to.not.emit(contract, 'IssueDAI');
+  });
+
 });
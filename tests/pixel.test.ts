@@ -30,4 +30,117 @@ describe('PatraPixel', () => {
       value: 300000000000
     })).to.emit(contract, 'PixelUpdate');
   });
+
+  it('repaint pixel pays previous owner', async () => {
+    const { contract, sender, Alice, one } = await setup();
+    await contract.tx.update([[1, 1]], { value: 10000000000 });
+    const price = await contract.query.priceOf(1);
+    expect(price.output?.toString()).to.equal('15000000000');
+    const painter = await getRandomSigner(Alice, one.muln(100));
+    await expect(contract.connect(painter).tx.update([[1, 2]], { value: 15000000000 }))
+      .to.emit(contract, 'PixelUpdate');
+    const owner = await contract.query.ownerOf(1);
+    expect(owner.output?.toString()).to.equal(painter.address);
+    const owned = await contract.query.pixelsOf(sender.address, 0, 100);
+    expect(owned.output?.toHuman()).to.deep.equal([]);
+    const count = await contract.query.pixelCountOf(painter.address);
+    expect(count.output?.toString()).to.equal('1');
+    const pool = await contract.query.pool();
+    expect(pool.output?.toString()).to.equal('11500000000');
+  });
+
+  it('reject invalid points', async () => {
+    const { contract, sender } = await setup();
+    const before = await api.query.system.account(contract.address);
+    await expect(contract.tx.update([[57600, 1]], { value: 10000000000 }))
+      .to.not.emit(contract, 'PixelUpdate');
+    await expect(contract.tx.update([[1, 0]], { value: 10000000000 }))
+      .to.not.emit(contract, 'PixelUpdate');
+    await expect(contract.tx.update([[1, 1], [1, 2]], { value: 20000000000 }))
+      .to.not.emit(contract, 'PixelUpdate');
+    // the painter gets the payment back and the pool is untouched
+    const after = await api.query.system.account(contract.address);
+    expect(after.data.free.toString()).to.equal(before.data.free.toString());
+    const pool = await contract.query.pool();
+    expect(pool.output?.toString()).to.equal('0');
+    const owned = await contract.query.pixelCountOf(sender.address);
+    expect(owned.output?.toString()).to.equal('0');
+  });
+
+  it('set palette', async () => {
+    const { contract, Alice, one } = await setup();
+    await contract.tx.setPalette([1, 200]);
+    const palette = await contract.query.palette();
+    expect(palette.output?.toHuman()).to.deep.equal(['1', '200']);
+    await expect(contract.tx.update([[1, 2]], { value: 10000000000 }))
+      .to.not.emit(contract, 'PixelUpdate');
+    await expect(contract.tx.update([[1, 200]], { value: 10000000000 }))
+      .to.emit(contract, 'PixelUpdate');
+    const stranger = await getRandomSigner(Alice, one.muln(10));
+    await contract.connect(stranger).tx.setPalette([3]);
+    const unchanged = await contract.query.palette();
+    expect(unchanged.output?.toHuman()).to.deep.equal(['1', '200']);
+  });
+
+  it('query region and export canvas', async () => {
+    const { contract } = await setup();
+    await contract.tx.update([[0, 1], [1, 1], [321, 2]], { value: 30000000000 });
+    const region = await contract.query.region(0, 0, 2, 2);
+    expect(region.output?.toJSON()).to.deep.equal({ ok: [1, 1, 0, 2] });
+    const page = await contract.query.metadataPage(1, 10);
+    expect(page.output?.toJSON()).to.deep.equal([[1, 1]]);
+    const nextPage = await contract.query.metadataPage(300, 100);
+    expect(nextPage.output?.toJSON()).to.deep.equal([[321, 2]]);
+    const runs = await contract.query.exportRle(0, 2);
+    expect(runs.output?.toJSON()).to.deep.equal([[2, 1], [319, 0], [1, 2], [318, 0]]);
+  });
+
+  it('withdraw pool to recipients', async () => {
+    const { contract, Alice, one } = await setup();
+    const treasury = await getRandomSigner(Alice, one.muln(10));
+    const artists = await getRandomSigner(Alice, one.muln(10));
+    await expect(contract.tx.setRecipients([[treasury.address, 7000], [artists.address, 2000]]))
+      .to.not.emit(contract, 'SetRecipients');
+    await expect(contract.tx.setRecipients([[treasury.address, 7000], [artists.address, 3000]]))
+      .to.emit(contract, 'SetRecipients');
+    await contract.tx.update([[1, 1]], { value: 10000000000 });
+    await expect(contract.tx.withdrawPool())
+      .to.emit(contract, 'PoolWithdraw')
+      .withArgs(treasury.address, 7000000000);
+    const pool = await contract.query.pool();
+    expect(pool.output?.toString()).to.equal('0');
+  });
+
+  it('query region at snapshot', async () => {
+    const { contract, sender } = await setup();
+    await contract.tx.update([[0, 1]], { value: 10000000000 });
+    await expect(contract.tx.snapshot())
+      .to.emit(contract, 'Snapshot');
+    await contract.tx.update([[0, 2]], { value: 15000000000 });
+    const region = await contract.query.regionAt(1, 0, 0, 2, 1);
+    expect(region.output?.toJSON()).to.deep.equal({ ok: [1, 0] });
+    const current = await contract.query.region(0, 0, 2, 1);
+    expect(current.output?.toJSON()).to.deep.equal({ ok: [2, 0] });
+    const history = await contract.query.historyOf(0);
+    expect(history.output?.toJSON()).to.have.lengthOf(2);
+    expect((history.output?.toJSON() as any)[1].painter).to.equal(sender.address);
+  });
+
+  it('keep the latest snapshot readable after pruning', async () => {
+    const { contract, Alice, one } = await setup();
+    const painter = await getRandomSigner(Alice, one.muln(100000));
+    await contract.connect(painter).tx.update([[0, 1]], { value: 10000000000 });
+    await contract.tx.snapshot();
+    // one more repaint than the history keeps
+    for (let i = 0; i <= 16; i++) {
+      const price = await contract.query.priceOf(0);
+      await contract.connect(painter).tx.update([[0, 2 + (i % 2)]], {
+        value: price.output?.toString()
+      });
+    }
+    const region = await contract.query.regionAt(1, 0, 0, 1, 1);
+    expect(region.output?.toJSON()).to.deep.equal({ ok: [1] });
+    const history = await contract.query.historyOf(0);
+    expect(history.output?.toJSON()).to.have.lengthOf(16);
+  });
 });
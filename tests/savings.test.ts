@@ -0,0 +1,106 @@
+import BN from 'bn.js';
+import { expect } from 'chai';
+import { patract, network, artifacts } from 'redspot';
+
+const { getContractFactory, getRandomSigner } = patract;
+
+const { api, getSigners } = network;
+
+// min governance delay of the maker, one minute
+const GOV_DELAY = 60000;
+
+function sleep(ms: number) {
+  return new Promise((resolve) => setTimeout(resolve, ms));
+}
+
+describe('Savings', () => {
+  after(() => {
+    return api.disconnect();
+  });
+
+  async function govern(contract, ...changes) {
+    for (const change of changes) {
+      await contract.tx.queueChange(change);
+    }
+    const count = await contract.query.proposalCount();
+    await sleep(GOV_DELAY + 6000);
+    const last = Number(count.output?.toString());
+    for (let id = last - changes.length + 1; id <= last; id++) {
+      await contract.tx.executeChange(id);
+    }
+  }
+
+  async function setup(...changes) {
+    const one = new BN(10).pow(new BN(api.registry.chainDecimals));
+    const signers = await getSigners();
+    const Alice = signers[0];
+    const sender = await getRandomSigner(Alice, one.muln(100));
+
+    const daiContractFactory = await getContractFactory('dai', sender);
+    const daiContract = await daiContractFactory.deployed('new', '1000000000000000000000', 'Maker DAI', 'DAI', '18');
+    const makerFactory = await getContractFactory('patramaker', sender);
+    const maker = await makerFactory.deploy('new', daiContract.address, GOV_DELAY, [sender.address]);
+    await daiContract.tx.addMinter(maker.address);
+    await maker.tx.submitPrice(0, 3500);
+    const contractFactory = await getContractFactory('savings', sender);
+    const contract = await contractFactory.deploy('new', daiContract.address, maker.address);
+    await daiContract.tx.approve(contract.address, '1000000000000000000000');
+    await govern(maker, { Savings: contract.address }, ...changes);
+    const abi = artifacts.readArtifact('savings');
+    const receiver = await getRandomSigner();
+
+    return { sender, contractFactory, contract, abi, receiver, Alice, one, daiContract, maker };
+  }
+
+  it('deposit dai', async () => {
+    const { contract, sender } = await setup();
+    await expect(contract.tx.deposit('1000000000000000000'))
+      .to.emit(contract, 'Deposit');
+    const total = await contract.query.totalDeposits();
+    expect(total.output?.toString()).to.equal('1000000000000000000');
+    const balance = await contract.query.balanceOf(sender.address);
+    expect(balance.output?.toString()).to.equal('1000000000000000000');
+  });
+
+  it('withdraw dai with interest', async () => {
+    const { contract, sender, maker } = await setup({ StabilityFee: 500 });
+    await maker.tx.issueDai(0, 1000000000000000, 200, {
+      value: 1000000000000000
+    });
+    await maker.tx.withdrawDot(1, 2000000000000000000);
+    await contract.tx.adjustRate(500);
+    await contract.tx.deposit('1000000000000000000');
+    await expect(contract.tx.drip())
+      .to.emit(maker, 'PayInterest');
+    const balance = await contract.query.balanceOf(sender.address);
+    expect(new BN(balance.output?.toString()).gt(new BN('1000000000000000000'))).to.be.true;
+    await expect(contract.tx.withdraw(balance.output))
+      .to.emit(contract, 'Withdraw');
+  });
+
+  it('cap interest by maker surplus', async () => {
+    const { contract, sender } = await setup();
+    await contract.tx.adjustRate(500);
+    await contract.tx.deposit('1000000000000000000');
+    await contract.tx.drip();
+    const balance = await contract.query.balanceOf(sender.address);
+    expect(balance.output?.toString()).to.equal('1000000000000000000');
+  });
+
+  it('refuse withdraw above deposit', async () => {
+    const { contract } = await setup();
+    await contract.tx.deposit('1000000000000000000');
+    await expect(contract.tx.withdraw('2000000000000000000'))
+      .to.not.emit(contract, 'Withdraw');
+  });
+
+  it('adjust savings rate', async () => {
+    const { contract } = await setup();
+    await expect(contract.tx.adjustRate(500))
+      .to.emit(contract, 'AdjustRate');
+    await expect(contract.tx.adjustRate(10001))
+      .to.not.emit(contract, 'AdjustRate');
+    const params = await contract.query.savingsParams();
+    expect(params.output?.toHuman()).to.deep.include('500');
+  });
+});